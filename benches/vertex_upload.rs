@@ -0,0 +1,101 @@
+//! Compares the vertex uploads of `TextBrush::queue()` and the staged
+//! `TextBrush::queue_staged()` for 10k+ glyphs of which one line changes per frame.
+//!
+//! Run with `cargo bench --bench vertex_upload`, needs a GPU adapter.
+
+use std::time::{Duration, Instant};
+
+use wgpu_text::{
+    glyph_brush::{ab_glyph::FontRef, Section, Text},
+    BrushBuilder, TextBrush,
+};
+
+const LINES: usize = 200;
+const LINE_LENGTH: usize = 60;
+const FRAMES: u32 = 200;
+
+/// Text of `LINES` lines, the line `frame % LINES` being different every frame.
+fn text(frame: u32) -> String {
+    let changed = frame as usize % LINES;
+    (0..LINES)
+        .map(|line| {
+            let first = match line == changed {
+                true => b'a' + (frame % 26) as u8,
+                false => b'A' + (line % 26) as u8,
+            };
+            (0..LINE_LENGTH)
+                .map(|i| (first + (i % 20) as u8) as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn section(text: &str) -> Section<'_> {
+    Section::default().add_text(Text::new(text).with_scale(8.0))
+}
+
+fn main() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(
+        instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+    );
+    let Some(adapter) = adapter else {
+        eprintln!("no adapter available, skipping");
+        return;
+    };
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&Default::default(), None)).unwrap();
+    let build = || {
+        BrushBuilder::using_font_bytes(include_bytes!("../examples/fonts/DejaVuSans.ttf"))
+            .unwrap()
+            .initial_cache_size((1024, 1024))
+            .build(&device, 1024, 1024, wgpu::TextureFormat::Rgba8Unorm)
+    };
+    let texts: Vec<String> = (0..FRAMES).map(text).collect();
+
+    let mut brush = build();
+    let (written, uploaded) = measure(&device, &queue, &mut brush, &texts, false);
+    let (staged, _) = measure(&device, &queue, &mut build(), &texts, true);
+
+    let glyphs = brush.vertices().len();
+    println!("{glyphs} glyphs, {FRAMES} frames");
+    println!("{} vertices uploaded per frame", uploaded / FRAMES as usize);
+    println!("queue:        {:?} per frame", written / FRAMES);
+    println!("queue_staged: {:?} per frame", staged / FRAMES);
+}
+
+/// Queues every text with the `brush`, `staged` or not, submitting the frame and
+/// waiting for the device each time, and returns the total time and number of
+/// uploaded vertices after a warm up frame.
+fn measure(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    brush: &mut TextBrush<FontRef<'static>>,
+    texts: &[String],
+    staged: bool,
+) -> (Duration, usize) {
+    let mut uploaded = 0;
+    let mut frame = |text: &str| {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        match staged {
+            true => {
+                brush
+                    .queue_staged(device, queue, &mut encoder, vec![section(text)])
+                    .unwrap();
+                brush.finish_staging();
+            }
+            false => brush.queue(device, queue, vec![section(text)]).unwrap(),
+        }
+        queue.submit([encoder.finish()]);
+        brush.recall_staging();
+        device.poll(wgpu::Maintain::Wait);
+        brush.last_frame_stats().vertices_uploaded
+    };
+    frame(&texts[texts.len() - 1]);
+    let start = Instant::now();
+    for text in texts {
+        uploaded += frame(text);
+    }
+    (start.elapsed(), uploaded)
+}
@@ -1,3 +1,6 @@
+// Mouse input handling is currently commented out, keep the API around for later.
+#![allow(dead_code, unused_variables)]
+
 use nalgebra::{Matrix4, Point3, Vector3};
 
 pub struct Camera {
@@ -112,7 +112,7 @@ fn main() {
                     } else {
                         size *= 4.0 / 5.0
                     };
-                    font_size = (size.max(3.0).min(2000.0) * 2.0).round() / 2.0;
+                    font_size = (size.clamp(3.0, 2000.0) * 2.0).round() / 2.0;
                 }
                 _ => (),
             },
@@ -119,21 +119,19 @@ fn main() {
                     }
                     _ => (),
                 },
-                WindowEvent::ReceivedCharacter(c) => {
-                    if c != '\u{7f}' && c != '\u{8}' {
-                        if section.text.is_empty() {
-                            section.text.push(
-                                OwnedText::default()
-                                    .with_scale(font_size)
-                                    .with_color([0.9, 0.5, 0.5, 1.0]),
-                            );
-                        }
+                WindowEvent::ReceivedCharacter(c) if c != '\u{7f}' && c != '\u{8}' => {
+                    if section.text.is_empty() {
                         section.text.push(
-                            OwnedText::new(c.to_string())
+                            OwnedText::default()
                                 .with_scale(font_size)
                                 .with_color([0.9, 0.5, 0.5, 1.0]),
                         );
                     }
+                    section.text.push(
+                        OwnedText::new(c.to_string())
+                            .with_scale(font_size)
+                            .with_color([0.9, 0.5, 0.5, 1.0]),
+                    );
                 }
                 WindowEvent::MouseWheel {
                     delta: winit::event::MouseScrollDelta::LineDelta(_, y),
@@ -146,7 +144,7 @@ fn main() {
                     } else {
                         size *= 4.0 / 5.0
                     };
-                    font_size = (size.max(3.0).min(25000.0) * 2.0).round() / 2.0;
+                    font_size = (size.clamp(3.0, 25000.0) * 2.0).round() / 2.0;
                 }
                 _ => (),
             },
@@ -1,187 +1,2876 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, ops::Range};
 
 use crate::{
+    cache::{SamplerFilters, TextureReadback},
+    effect::{EffectSlot, EffectUniform},
+    emoji::ColorAtlas,
     error::BrushError,
-    pipeline::{Pipeline, Vertex},
-    Matrix,
+    pipeline::{BlendMode, Instance, Pipeline, PipelineConfig, Vertex, VertexStyle},
+    section::{glyph_section, GlyphExtra, Jitter, Transform2D},
+    shaping::ShapedRun,
+    FadeEdge, GradientDirection, LayoutMode, Matrix, StyledSection, VerticalRunAlign,
+    YAxis,
 };
 use glyph_brush::{
-    ab_glyph::{Font, FontArc, FontRef, InvalidFont, Rect},
-    BrushAction, DefaultSectionHasher, Extra, GlyphCruncher, Section, SectionGlyphIter,
+    ab_glyph::{
+        point, Font, FontArc, FontRef, GlyphId, InvalidFont, Point, PxScale, Rect,
+        ScaleFont, VariableFont,
+    },
+    BrushAction, Color, DefaultSectionHasher, Extra, FontId, GlyphCruncher,
+    GlyphPositioner, GlyphVertex, HorizontalAlign, Layout, OwnedSection, OwnedText,
+    Rectangle, Section, SectionGeometry, SectionGlyph, SectionGlyphIter,
 };
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
 
 /// Wrapper over [`glyph_brush::GlyphBrush`]. In charge of drawing text.
 ///
 /// Used for queuing and rendering text with [`TextBrush::draw`].
 pub struct TextBrush<F = FontArc, H = DefaultSectionHasher> {
-    inner: glyph_brush::GlyphBrush<Vertex, Extra, F, H>,
+    inner: glyph_brush::GlyphBrush<Instance, GlyphExtra, F, H>,
     pipeline: Pipeline,
+    sanitize_control_chars: bool,
+    /// Fonts searched in order for characters missing in the font of their text.
+    fallback_fonts: Vec<FontId>,
+    hinting: Hinting,
+    cache_growth: GrowthPolicy,
+    subpixel: bool,
+    scale_factor: f32,
+    depth_layers: u32,
+    y_axis: YAxis,
+    /// `max_texture_dimension_2d` limit of the device the brush was built with.
+    max_texture_dimension: u32,
+    /// Dimensions the cache texture may grow to, set with
+    /// [`BrushBuilder::with_max_cache_size`].
+    max_cache_size: Option<(u32, u32)>,
+    /// Rebuilds `inner`, dropping the sections it keeps queued after they didn't fit
+    /// into the cache texture. Set with [`BrushBuilder::with_max_cache_size`].
+    rebuild: Option<RebuildFn<F, H>>,
+    /// Cache texture dimensions the brush was built with.
+    initial_cache_size: (u32, u32),
+    /// Incremented with every cache texture change, which makes [`StaticText`] stale.
+    cache_generation: u64,
+    /// Cache statistics of the last [`TextBrush::upload`].
+    cache_stats: CacheStats,
+    frame_stats: FrameStats,
+    /// Last orthographic matrix computed by [`TextBrush::resize_view`] and its size.
+    ortho: Option<((f32, f32), Matrix)>,
+    /// View matrix set with [`TextBrush::update_matrix`], before the transform.
+    view_matrix: Matrix,
+    /// Transform set with [`TextBrush::set_transform`].
+    transform: Option<Matrix>,
+
+    /// Glyph vertices of the last processed queue and solid quads drawn under them.
+    text_vertices: Vec<Instance>,
+    quads: Vec<Instance>,
+    /// Uploaded vertices, quads followed by the glyph vertices, and their styles, only
+    /// filled if any vertex is styled.
+    vertices: Vec<Vertex>,
+    styles: Vec<VertexStyle>,
+
+    /// Single glyphs queued with [`TextBrush::queue_glyph`] for the next `queue` call.
+    glyphs: Vec<SectionGlyph>,
+    glyph_extra: Vec<GlyphExtra>,
+    /// Solid quads queued with [`TextBrush::queue_highlight`] for the next `queue` call.
+    highlights: Vec<Instance>,
+    /// Vertices queued with [`TextBrush::queue_raw`] for the next `queue` call.
+    raw_vertices: Vec<Instance>,
+    /// Color glyph cache of [`BrushBuilder::with_color_emoji`] (and of
+    /// [`BrushBuilder::prefer_bitmap_strikes`]) and the color glyph vertices of the
+    /// current queue.
+    color_atlas: Option<ColorAtlas>,
+    color_vertices: Vec<Instance>,
+    /// Belt of [`TextBrush::queue_staged`], created on first use.
+    staging_belt: Option<wgpu::util::StagingBelt>,
+
+    /// Sections kept resident while trimming is paused.
+    resident: Option<Vec<OwnedSection<GlyphExtra>>>,
+}
+
+impl<F, H> TextBrush<F, H>
+where
+    F: Font + Sync,
+    H: std::hash::BuildHasher,
+{
+    /// Queues section for drawing, processes all queued text and updates the
+    /// inner vertex buffer, unless the text vertices remain unmodified when
+    /// compared to the last frame. Only the range of vertices that changed is written.
+    ///
+    /// Sections can be plain [`Section`]s or [`StyledSection`]s with additional options
+    /// (see [`SectionExt`](crate::SectionExt)).
+    ///
+    /// If utilizing *depth*, the `sections` list should have `Section`s ordered from
+    /// furthest to closest. They will be drawn in the order they are given, unless
+    /// reordered with [`SectionExt::order()`](crate::SectionExt::order).
+    ///
+    /// - This method should be called every frame, once, before all draw calls of the
+    ///   frame.
+    ///
+    /// If not called when required, the draw functions will continue drawing data from the
+    /// inner vertex buffer meaning they will redraw old vertices. Drawing the same text
+    /// into several render passes only needs a single call, see [`Self::draw()`].
+    ///
+    /// Equal to [`Self::prepare()`] followed by [`Self::upload()`].
+    ///
+    /// To learn about GPU texture caching, see
+    /// [`caching behaviour`](https://docs.rs/glyph_brush/latest/glyph_brush/struct.GlyphBrush.html#caching-behaviour)
+    #[inline]
+    pub fn queue<'a, S>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sections: Vec<S>,
+    ) -> Result<(), BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let prepared = self.prepare(sections)?;
+        self.upload(device, queue, prepared);
+        Ok(())
+    }
+
+    /// Queues `sections` and runs all CPU work of [`queue`](#method.queue), the layout,
+    /// glyph rasterization and vertex generation, without touching the GPU. The
+    /// returned [`PreparedText`] holds the vertices and cache texture updates, which
+    /// are applied with [`Self::upload()`].
+    ///
+    /// Doesn't need a [`wgpu::Device`], so it can run on a preparation thread (the brush
+    /// has to be moved or shared there) while the render thread only calls
+    /// [`Self::upload()`] and draws. Every prepared text has to be uploaded, in the
+    /// order of preparation, before preparing the next one, as it only holds the cache
+    /// texture changes since the previous preparation.
+    ///
+    /// Fails with [`BrushError::UnknownFont`] without queueing anything if a section
+    /// uses a font id the brush has no font for. Highlights and raw vertices (see
+    /// [`Self::queue_highlight()`] and [`Self::queue_raw()`]) stay queued for the next
+    /// call when preparing fails.
+    pub fn prepare<'a, S>(&mut self, sections: Vec<S>) -> Result<PreparedText, BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let mut sections: Vec<StyledSection> =
+            sections.into_iter().map(Into::into).collect();
+        let fonts = self.inner.fonts().len();
+        for (index, s) in sections.iter().enumerate() {
+            if let Some(text) = s.section.text.iter().find(|t| t.font_id.0 >= fonts) {
+                return Err(BrushError::UnknownFont(index, text.font_id));
+            }
+        }
+        sections.sort_by_key(|s| s.order);
+        if let Some(atlas) = &mut self.color_atlas {
+            atlas.begin_frame();
+        }
+
+        // Queue sections:
+        let mut quads = Vec::new();
+        for mut s in sections {
+            let line_backgrounds = s.line_backgrounds;
+            let line_height = s.line_height;
+            let decorations = (s.underline, s.strikethrough);
+            // Laid out at the origin, moved to the section position on the GPU.
+            let local_origin = s.local_origin.then(|| {
+                let (x, y) = std::mem::take(&mut s.section.to_mut().screen_position);
+                (x, self.flip_y(y))
+            });
+            let clip = s.clip().map(|clip| {
+                let clip = self.flip_rect(clip);
+                let (x, y) = local_origin.unwrap_or_default();
+                Rect {
+                    min: point(clip.min.x - x, clip.min.y - y),
+                    max: point(clip.max.x - x, clip.max.y - y),
+                }
+            });
+            let mut adjustments = GlyphAdjustments {
+                reveal: s.reveal.unwrap_or(1.0),
+                run_align: s.vertical_run_align,
+                hinting: self.hinting,
+                jitter: s.jitter,
+                subpixel: self.subpixel,
+                edge_fades: s.edge_fades,
+                tabular_figures: s.tabular_figures,
+                letter_spacing: s.letter_spacing,
+                tab_width: s.tab_width,
+                line_height,
+                layout_mode: s.layout_mode,
+                shadow: s.shadow,
+                gradient: s.gradient,
+                transform: s.transform,
+                local_origin,
+                clip,
+                color_glyphs: self.color_atlas.is_some(),
+                ..GlyphAdjustments::NONE
+            };
+            let prepared = self.prepare_section(s);
+            let section = prepared.section();
+            if let Some(resident) = &mut self.resident {
+                if !resident.iter().any(|r| r.to_borrowed() == section) {
+                    resident.push(section.to_owned());
+                }
+            }
+            let first_quad = quads.len();
+            if !line_backgrounds.is_empty() {
+                self.line_background_quads(
+                    &section,
+                    line_backgrounds,
+                    line_height,
+                    &mut quads,
+                );
+            }
+            if decorations != (None, None) {
+                self.decoration_quads(&section, decorations, line_height, &mut quads);
+            }
+            if let Some(origin) = local_origin {
+                for quad in &mut quads[first_quad..] {
+                    quad.translate(origin);
+                }
+            }
+            adjustments.hide_zero_width_spaces = self.sanitize_control_chars
+                && section
+                    .text
+                    .iter()
+                    .any(|t| t.text.contains(ZERO_WIDTH_SPACE));
+            match adjustments == GlyphAdjustments::NONE {
+                true => self.inner.queue(section),
+                false => self.queue_glyphs(&section, adjustments),
+            }
+        }
+        if !self.glyphs.is_empty() {
+            let bounds = Rect {
+                min: point(f32::NEG_INFINITY, f32::NEG_INFINITY),
+                max: point(f32::INFINITY, f32::INFINITY),
+            };
+            let mut glyphs = std::mem::take(&mut self.glyphs);
+            let mut extra = std::mem::take(&mut self.glyph_extra);
+            self.queue_color_glyphs(&mut glyphs, &mut extra, bounds);
+            self.inner.queue_pre_positioned(glyphs, extra, bounds);
+        }
+        if let Some(resident) = &self.resident {
+            for section in resident {
+                self.inner.keep_cached(section);
+            }
+        }
+
+        // Process sections:
+        let mut prepared = PreparedText {
+            vertices: None,
+            quads: Vec::new(),
+            texture_updates: Vec::new(),
+            color_updates: Vec::new(),
+            cache_size: None,
+        };
+        // The cache texture only grows with the upload of the prepared text.
+        let dimensions = self.inner.texture_dimensions();
+        loop {
+            // Contains BrushAction enum which marks for
+            // drawing or redrawing (using old data).
+            let brush_action = self.inner.process_queued(
+                |rect, data| prepared.texture_updates.push((rect, data.to_vec())),
+                Instance::to_vertex,
+            );
+
+            match brush_action {
+                Ok(action) => {
+                    break match action {
+                        BrushAction::Draw(mut vertices) => {
+                            // Glyphs fully outside of their section bounds.
+                            vertices.retain(Instance::is_visible);
+                            prepared.vertices = Some(vertices);
+                        }
+                        BrushAction::ReDraw => (),
+                    };
+                }
+
+                Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
+                    let suggested = self
+                        .cache_growth
+                        .grow(self.inner.texture_dimensions(), suggested);
+                    if log::log_enabled!(log::Level::Warn) {
+                        log::warn!(
+                            "Resizing cache texture! This should be avoided \
+                            by building TextBrush with BrushBuilder::initial_cache_size() \
+                            and providing bigger cache texture dimensions."
+                        );
+                    }
+                    // Texture resizing:
+                    let limit = self.max_texture_dimension;
+                    let (max_width, max_height) = match self.max_cache_size {
+                        Some((width, height)) => (width.min(limit), height.min(limit)),
+                        None => (limit, limit),
+                    };
+                    let current = self.inner.texture_dimensions();
+                    let (width, height) =
+                        (suggested.0.min(max_width), suggested.1.min(max_height));
+                    if width <= current.0 && height <= current.1 {
+                        if current != dimensions {
+                            self.inner.resize_texture(dimensions.0, dimensions.1);
+                        }
+                        // Recreated from the sections when they're queued again.
+                        self.color_vertices.clear();
+                        self.drop_queued();
+                        return Err(match self.max_cache_size {
+                            Some((width, height)) => {
+                                BrushError::CacheSizeExceeded(width, height)
+                            }
+                            None => BrushError::TooBigCacheTexture(limit),
+                        });
+                    }
+                    self.inner.resize_texture(width, height);
+                    // The resized texture is filled from scratch.
+                    prepared.texture_updates.clear();
+                    prepared.cache_size = Some((width, height));
+                }
+            }
+        }
+        // Taken once nothing can fail, they're uploaded by a later call otherwise.
+        if let Some(atlas) = &mut self.color_atlas {
+            prepared.color_updates = atlas.take_updates();
+        }
+        // Highlights under the section quads, color glyphs and raw vertices over them.
+        prepared.quads = std::mem::take(&mut self.highlights);
+        prepared.quads.append(&mut quads);
+        self.color_vertices.retain(Instance::is_visible);
+        prepared.quads.append(&mut self.color_vertices);
+        prepared.quads.append(&mut self.raw_vertices);
+
+        Ok(prepared)
+    }
+
+    /// Drops the sections **glyph_brush** keeps queued after they didn't fit into the
+    /// cache texture, which would make every later queue fail too. Cached glyphs are
+    /// dropped with them, keeping the cache texture dimensions.
+    fn drop_queued(&mut self) {
+        if let Some(rebuild) = self.rebuild {
+            rebuild(&mut self.inner);
+            self.cache_generation += 1;
+            if let Some(resident) = &mut self.resident {
+                resident.clear();
+            }
+        }
+    }
+
+    /// Applies the cache texture updates of text prepared with [`Self::prepare()`]
+    /// and updates the inner vertex buffer with its vertices.
+    #[inline]
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        prepared: PreparedText,
+    ) {
+        self.upload_with(device, queue, None, prepared);
+    }
+
+    /// Like [`queue`](#method.queue), but copies the vertices into the inner vertex
+    /// buffer through a [`wgpu::util::StagingBelt`] owned by the brush, recording the
+    /// copy into the `encoder`.
+    ///
+    /// Unlike `queue`, which lets **wgpu** allocate a new staging buffer for every
+    /// vertex upload, the belt reuses its staging chunks across frames. Whether that's
+    /// faster depends on the backend, see the `vertex_upload` benchmark. Like `queue`,
+    /// only the range of vertices that changed since the last upload is written. Call [`Self::finish_staging()`] after the
+    /// last staged call of a frame, before submitting the `encoder`, and
+    /// [`Self::recall_staging()`] after submitting it.
+    pub fn queue_staged<'a, S>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        sections: Vec<S>,
+    ) -> Result<(), BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let prepared = self.prepare(sections)?;
+        self.upload_with(device, queue, Some(encoder), prepared);
+        Ok(())
+    }
+
+    /// Like [`Self::upload()`], but stages the vertices like [`Self::queue_staged()`].
+    #[inline]
+    pub fn upload_staged(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        prepared: PreparedText,
+    ) {
+        self.upload_with(device, queue, Some(encoder), prepared);
+    }
+
+    /// Closes the staging chunks written by [`Self::queue_staged()`] calls, call it
+    /// before submitting their command encoder.
+    #[inline]
+    pub fn finish_staging(&mut self) {
+        if let Some(belt) = &mut self.staging_belt {
+            belt.finish();
+        }
+    }
+
+    /// Recalls the staging chunks of [`Self::queue_staged()`] calls for reuse, call it
+    /// after submitting their command encoder.
+    #[inline]
+    pub fn recall_staging(&mut self) {
+        if let Some(belt) = &mut self.staging_belt {
+            belt.recall();
+        }
+    }
+
+    /// Uploads the `prepared` text, staging the vertices with the `encoder` if any.
+    fn upload_with(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: Option<&mut wgpu::CommandEncoder>,
+        prepared: PreparedText,
+    ) {
+        self.cache_stats.resized = prepared.cache_size.is_some();
+        if let Some(size) = prepared.cache_size {
+            self.pipeline.resize_texture(device, size);
+            self.cache_generation += 1;
+            self.cache_stats.resizes += 1;
+        }
+        self.cache_stats.texture_dimensions = self.inner.texture_dimensions();
+        self.cache_stats.uploaded_glyphs = prepared.texture_updates.len();
+        self.cache_stats.uploaded_texels = prepared
+            .texture_updates
+            .iter()
+            .map(|(rect, _)| rect.width() as u64 * rect.height() as u64)
+            .sum();
+        if !prepared.texture_updates.is_empty() {
+            for (rect, data) in &prepared.texture_updates {
+                self.pipeline.update_texture(*rect, data, queue);
+            }
+            self.cache_generation += 1;
+        }
+        if !prepared.color_updates.is_empty() {
+            for (rect, data) in &prepared.color_updates {
+                self.pipeline.update_color_texture(*rect, data, queue);
+            }
+            self.cache_generation += 1;
+        }
+
+        let redraw = prepared.vertices.is_some();
+        if let Some(vertices) = prepared.vertices {
+            self.text_vertices = vertices;
+            let bounds = self.text_bounds();
+            self.pipeline
+                .update_effect_bounds(EffectSlot::Shimmer, bounds, queue);
+        }
+        let instances = prepared.quads.len() + self.text_vertices.len();
+        self.frame_stats = FrameStats {
+            instances: instances as u32,
+            cache_resized: self.cache_stats.resized,
+            vertices_uploaded: 0,
+        };
+        if redraw || prepared.quads != self.quads {
+            self.quads = prepared.quads;
+            let instances = || self.quads.iter().chain(&self.text_vertices);
+            let mut changed =
+                overwrite(&mut self.vertices, instances().map(|i| i.vertex));
+            match instances().any(Instance::is_styled) {
+                true => {
+                    let styles =
+                        overwrite(&mut self.styles, instances().map(|i| i.style));
+                    changed =
+                        changed.start.min(styles.start)..changed.end.max(styles.end);
+                }
+                false => self.styles.clear(),
+            }
+            let buffers = (self.vertices.as_slice(), self.styles.as_slice());
+            let written = match encoder {
+                Some(encoder) => {
+                    let belt = self.staging_belt.get_or_insert_with(|| {
+                        wgpu::util::StagingBelt::new(STAGING_CHUNK_SIZE)
+                    });
+                    self.pipeline.update_vertex_buffer_staged(
+                        buffers, changed, device, encoder, belt,
+                    )
+                }
+                None => self
+                    .pipeline
+                    .update_vertex_buffer(buffers, changed, device, queue),
+            };
+            self.frame_stats.vertices_uploaded = written.len();
+        }
+    }
+
+    /// Returns a section drawing `text` at `position` (top left corner) in the
+    /// provided `color` and `scale` (font size in pixels), using the first font.
+    ///
+    /// Shorthand for the most common case, [`Section`] and
+    /// [`Text`](glyph_brush::Text) builders are still available for anything more
+    /// elaborate. The section can be modified further before queuing it.
+    #[inline]
+    pub fn simple<'a>(
+        &self,
+        text: &'a str,
+        position: (f32, f32),
+        color: Color,
+        scale: f32,
+    ) -> Section<'a> {
+        Section::default().with_screen_position(position).add_text(
+            glyph_brush::Text::new(text)
+                .with_color(color)
+                .with_scale(scale),
+        )
+    }
+
+    /// Queues a single glyph, like an icon from an icon font, bypassing text layout. The
+    /// glyph is cached in the same cache texture and drawn by the same pipeline as
+    /// regular text.
+    ///
+    /// `position` is the glyph origin, on its baseline. Queued glyphs are drawn with the
+    /// sections of the next [`queue`](#method.queue) call, on top of them.
+    ///
+    /// Fails with [`BrushError::UnknownGlyphFont`] without queueing the glyph if the
+    /// brush has no font with `font_id`.
+    pub fn queue_glyph<S>(
+        &mut self,
+        glyph_id: GlyphId,
+        font_id: FontId,
+        scale: S,
+        position: (f32, f32),
+        color: Color,
+    ) -> Result<(), BrushError>
+    where
+        S: Into<PxScale>,
+    {
+        let glyph = SectionGlyph {
+            section_index: 0,
+            byte_index: 0,
+            glyph: glyph_id.with_scale_and_position(scale, point(position.0, position.1)),
+            font_id,
+        };
+        self.queue_positioned([glyph], Extra { color, z: 0.0 })
+    }
+
+    /// Queues already positioned glyphs, bypassing text layout, with the same `extra`
+    /// (color and *z*). Like [`Self::queue_glyph()`], queued glyphs are drawn with the
+    /// sections of the next [`queue`](#method.queue) call, on top of them.
+    ///
+    /// This is the hook for glyphs positioned by an external text shaper. The built-in
+    /// layout of **glyph_brush** only places glyphs one after another by their advances
+    /// (with kerning), it doesn't apply the positioning of the font's `GPOS` table.
+    /// Combining diacritical marks are therefore drawn where the font outline puts them,
+    /// which is usually fine for a single mark, but stacked marks can overlap. A shaper
+    /// applies mark-to-base and mark-to-mark positioning, its glyphs can be queued here.
+    ///
+    /// Fails with [`BrushError::UnknownGlyphFont`] without queueing any of the glyphs
+    /// if one uses a font id the brush has no font for.
+    pub fn queue_positioned<I>(
+        &mut self,
+        glyphs: I,
+        extra: Extra,
+    ) -> Result<(), BrushError>
+    where
+        I: IntoIterator<Item = SectionGlyph>,
+    {
+        let (first, fonts) = (self.glyphs.len(), self.inner.fonts().len());
+        for mut glyph in glyphs {
+            if glyph.font_id.0 >= fonts {
+                self.glyphs.truncate(first);
+                self.glyph_extra.truncate(first);
+                return Err(BrushError::UnknownGlyphFont(glyph.font_id));
+            }
+            glyph.glyph.position.y = self.flip_y(glyph.glyph.position.y);
+            if self.subpixel {
+                glyph.glyph.scale.x *= SUBPIXELS;
+                glyph.glyph.position.x *= SUBPIXELS;
+            }
+            self.glyphs.push(glyph);
+            self.glyph_extra.push(extra.into());
+        }
+        Ok(())
+    }
+
+    /// Queues a solid `rect` of `color` (in the same pixel coordinates as sections),
+    /// like the selection highlight of a text editor, drawn with the sections of the
+    /// next [`queue`](#method.queue) call, under all of them.
+    ///
+    /// Highlights share the vertex buffer and draw calls with the text. Rectangles of
+    /// character ranges can be computed from [`Self::glyph_rects()`], overlapping
+    /// highlights are blended by the brush [`BlendMode`].
+    pub fn queue_highlight(&mut self, rect: Rect, color: Color) {
+        let x_scale = if self.subpixel { SUBPIXELS } else { 1.0 };
+        let rect = self.flip_rect(rect);
+        let rect = Rect {
+            min: point(rect.min.x * x_scale, rect.min.y),
+            max: point(rect.max.x * x_scale, rect.max.y),
+        };
+        self.highlights.push(Instance::solid(rect, 0.0, color));
+    }
+
+    /// Queues externally computed `vertices`, like ones of an earlier
+    /// [`Self::vertices()`] call or procedurally generated glyphs, drawn with the
+    /// sections of the next [`queue`](#method.queue) call, under all of them. They
+    /// bypass layout entirely and are uploaded as they are.
+    ///
+    /// Vertex texture coordinates sample the cache texture as it is, so they're only
+    /// valid for glyphs which are cached at those coordinates, e.g. by queuing the
+    /// sections they were taken from again. Cached glyphs move when the cache texture
+    /// is resized or cleared, see [`Self::cache_stats()`]. Solid quads (negative
+    /// texture coordinates) don't sample the cache. Raw vertices have no
+    /// [`VertexStyle`], they aren't rotated or transformed.
+    pub fn queue_raw(&mut self, vertices: Vec<Vertex>) {
+        self.raw_vertices
+            .extend(vertices.into_iter().map(Instance::from));
+    }
+
+    /// Queues a [`ShapedRun`] of glyphs positioned by an external text shaper, with the
+    /// same `extra` (color and *z*). See [`Self::queue_positioned()`], including its
+    /// errors.
+    #[inline]
+    pub fn queue_shaped(
+        &mut self,
+        run: &ShapedRun,
+        extra: Extra,
+    ) -> Result<(), BrushError> {
+        self.queue_positioned(run.glyphs().iter().cloned(), extra)
+    }
+
+    /// Returns a bounding box for the section glyphs calculated using each
+    /// glyph's vertical & horizontal metrics. For more info, read about
+    /// [`GlyphCruncher::glyph_bounds`].
+    ///
+    /// Sections with [`SectionExt::tabular_figures()`](crate::SectionExt::tabular_figures)
+    /// are measured with the tabular digit advances, sections with
+    /// [`SectionExt::letter_spacing()`](crate::SectionExt::letter_spacing) with the
+    /// spacing, sections with [`SectionExt::tab_width()`](crate::SectionExt::tab_width)
+    /// with the tab stops, sections with
+    /// [`SectionExt::line_height()`](crate::SectionExt::line_height) with the adjusted
+    /// lines and [`LayoutMode::Vertical`] sections in their columns.
+    #[inline]
+    pub fn glyph_bounds<'a, S>(&mut self, section: S) -> Option<Rect>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let section = section.into();
+        let tabular_figures = section.tabular_figures;
+        let letter_spacing = section.letter_spacing;
+        let tab_width = section.tab_width;
+        let line_height = section.line_height;
+        let layout_mode = section.layout_mode;
+        let clip = section.clip().map(|clip| self.flip_rect(clip));
+        let prepared = self.prepare_section(section);
+        if !tabular_figures
+            && letter_spacing == 0.0
+            && tab_width <= 0.0
+            && line_height == 1.0
+            && layout_mode == LayoutMode::Horizontal
+            && clip.is_none()
+        {
+            let bounds = self.inner.glyph_bounds(prepared.section());
+            return bounds.map(|bounds| self.flip_rect(bounds));
+        }
+
+        let section = prepared.section();
+        let mut glyphs: Vec<SectionGlyph> =
+            self.inner.glyphs(&section).cloned().collect();
+        if tabular_figures {
+            self.tabular_figures(&section, &mut glyphs);
+        }
+        if tab_width > 0.0 {
+            self.tab_stops(&section, &mut glyphs, tab_width);
+        }
+        if letter_spacing != 0.0 {
+            letter_spacing_glyphs(&section, &mut glyphs, letter_spacing);
+        }
+        if line_height != 1.0 {
+            line_height_glyphs(&mut glyphs, line_height);
+        }
+        if layout_mode == LayoutMode::Vertical {
+            self.vertical_layout(&section, &mut glyphs);
+        }
+        let bounds = self.bounds(&section, &glyphs, clip);
+        bounds.map(|bounds| self.flip_rect(bounds))
+    }
+
+    /// Measures the section like [`Self::glyph_bounds()`], without needing mutable
+    /// access to the brush, e.g. for sizing buttons or centering UI before queuing.
+    ///
+    /// The section is laid out from scratch, bypassing the layout cache of
+    /// [`Self::glyph_bounds()`], so prefer that one for repeatedly measured sections.
+    /// The returned rectangle is in the same pixel coordinates as the section position.
+    pub fn measure<'a, S>(&self, section: S) -> Option<Rect>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let section = section.into();
+        let clip = section.clip().map(|clip| self.flip_rect(clip));
+        let (prepared, glyphs) = self.layout(section);
+        let bounds = self.bounds(&prepared.section(), &glyphs, clip);
+        bounds.map(|bounds| self.flip_rect(bounds))
+    }
+
+    /// Returns the laid out glyphs of the section with their pixel rectangles, for hit
+    /// testing and caret placement in text editing widgets. The `byte_index` of each
+    /// glyph maps it back to the section text.
+    ///
+    /// Like [`Self::measure()`], it doesn't need mutable access to the brush. Glyph
+    /// rectangles are the glyph layout bounds, covering the advance horizontally and
+    /// ascent to descent vertically, so they tile lines of text without gaps.
+    pub fn glyph_rects<'a, S>(&self, section: S) -> Vec<GlyphRect>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let (_, glyphs) = self.layout(section.into());
+        let fonts = self.inner.fonts();
+        glyphs
+            .into_iter()
+            .map(|mut glyph| {
+                let rect = fonts[glyph.font_id.0]
+                    .as_scaled(glyph.glyph.scale)
+                    .glyph_bounds(&glyph.glyph);
+                glyph.glyph.position.y = self.flip_y(glyph.glyph.position.y);
+                GlyphRect {
+                    rect: self.flip_rect(rect),
+                    glyph,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the glyph of the section whose rectangle (see [`Self::glyph_rects()`])
+    /// contains the `point`, if any.
+    pub fn glyph_at<'a, S>(&self, section: S, point: Point) -> Option<GlyphRect>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        self.glyph_rects(section).into_iter().find(|g| {
+            (g.rect.min.x..g.rect.max.x).contains(&point.x)
+                && (g.rect.min.y..g.rect.max.y).contains(&point.y)
+        })
+    }
+
+    /// Returns the one pixel wide rectangle where a text editing caret should be drawn
+    /// for the insertion point at `byte_index`, in the same pixel coordinates as the
+    /// section position. Texts of sections with multiple [`Text`](glyph_brush::Text)s
+    /// are indexed as if they were concatenated.
+    ///
+    /// The caret is placed before the glyph at `byte_index`, or after the preceding
+    /// glyph at line ends, and spans the font ascent to descent. Insertion points after
+    /// line breaks, on empty lines and in empty text are placed at the section
+    /// position horizontally. Indices within a character are moved to its start and
+    /// indices past the text end to the end.
+    ///
+    /// Returns `None` if the section has no text at all.
+    pub fn caret_rect<'a, S>(&self, section: S, byte_index: usize) -> Option<Rect>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        self.layout_caret_rect(section.into(), byte_index)
+            .map(|rect| self.flip_rect(rect))
+    }
+
+    /// [`Self::caret_rect()`] in the y-down layout space.
+    fn layout_caret_rect(
+        &self,
+        section: StyledSection,
+        byte_index: usize,
+    ) -> Option<Rect> {
+        let (prepared, glyphs) = self.layout(section);
+        let section = prepared.section();
+        let fonts = self.inner.fonts();
+        let first = section.text.first()?;
+
+        // Byte offsets of the section texts within their concatenation.
+        let mut offsets = Vec::with_capacity(section.text.len());
+        let mut text = String::new();
+        for t in &section.text {
+            offsets.push(text.len());
+            text.push_str(t.text);
+        }
+        let mut index = byte_index.min(text.len());
+        while !text.is_char_boundary(index) {
+            index -= 1;
+        }
+        let start =
+            |glyph: &SectionGlyph| offsets[glyph.section_index] + glyph.byte_index;
+        let caret = |x: f32, baseline: f32, font_id: FontId, scale: PxScale| {
+            let scaled = fonts[font_id.0].as_scaled(scale);
+            Rect {
+                min: point(x, baseline - scaled.ascent()),
+                max: point(x + 1.0, baseline - scaled.descent()),
+            }
+        };
+        let line_height = |font_id: FontId, scale: PxScale| {
+            let scaled = fonts[font_id.0].as_scaled(scale);
+            scaled.ascent() - scaled.descent() + scaled.line_gap()
+        };
+        let line_breaks = |range: Range<usize>| text[range].matches('\n').count() as f32;
+
+        if let Some(glyph) = glyphs.iter().find(|glyph| start(glyph) == index) {
+            let position = glyph.glyph.position;
+            return Some(caret(
+                position.x,
+                position.y,
+                glyph.font_id,
+                glyph.glyph.scale,
+            ));
+        }
+
+        let x = section.screen_position.0;
+        let previous = glyphs
+            .iter()
+            .filter(|glyph| start(glyph) < index)
+            .max_by_key(|glyph| start(glyph));
+        let rect = match previous {
+            Some(previous) => {
+                let (font_id, scale) = (previous.font_id, previous.glyph.scale);
+                let position = previous.glyph.position;
+                let breaks = line_breaks(start(previous)..index);
+                if breaks > 0.0 {
+                    let baseline = position.y + breaks * line_height(font_id, scale);
+                    caret(x, baseline, font_id, scale)
+                } else {
+                    let scaled = fonts[font_id.0].as_scaled(scale);
+                    let end = position.x + scaled.h_advance(previous.glyph.id);
+                    caret(end, position.y, font_id, scale)
+                }
+            }
+            // Leading line breaks or empty text.
+            None => {
+                let (font_id, scale) = (first.font_id, first.scale);
+                let height = line_height(font_id, scale);
+                let first_baseline = match glyphs.iter().min_by_key(|g| start(g)) {
+                    Some(glyph) => {
+                        glyph.glyph.position.y - line_breaks(0..start(glyph)) * height
+                    }
+                    None => {
+                        let scaled = fonts[font_id.0].as_scaled(scale);
+                        section.screen_position.1 + scaled.ascent()
+                    }
+                };
+                let baseline = first_baseline + line_breaks(0..index) * height;
+                caret(x, baseline, font_id, scale)
+            }
+        };
+        Some(rect)
+    }
+
+    /// Returns the insertion byte index nearest to the `point`, like the caret position
+    /// of a mouse click, the inverse of [`Self::caret_rect()`]. Texts of sections with
+    /// multiple [`Text`](glyph_brush::Text)s are indexed as if they were concatenated.
+    ///
+    /// The line vertically closest to the `point` is picked, then the glyph boundary
+    /// horizontally closest to it: clicks on the left half of a glyph insert before it
+    /// and clicks on its right half after it. Points before or after a line are clamped
+    /// to its start or end. Lines without any glyphs can't be hit and sections without
+    /// glyphs return `0`.
+    pub fn index_at<'a, S>(&self, section: S, point: Point) -> usize
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let point = Point {
+            x: point.x,
+            y: self.flip_y(point.y),
+        };
+        let (prepared, glyphs) = self.layout(section.into());
+        let section = prepared.section();
+        let fonts = self.inner.fonts();
+
+        let mut offsets = Vec::with_capacity(section.text.len());
+        let mut len = 0;
+        for t in &section.text {
+            offsets.push(len);
+            len += t.text.len();
+        }
+        let start =
+            |glyph: &SectionGlyph| offsets[glyph.section_index] + glyph.byte_index;
+        let end = |glyph: &SectionGlyph| {
+            let text = section.text[glyph.section_index].text;
+            let char_len = text[glyph.byte_index..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            start(glyph) + char_len
+        };
+        let rect = |glyph: &SectionGlyph| {
+            fonts[glyph.font_id.0]
+                .as_scaled(glyph.glyph.scale)
+                .glyph_bounds(&glyph.glyph)
+        };
+        let distance = |min: f32, max: f32, v: f32| (min - v).max(v - max).max(0.0);
+
+        // Extent of the glyph vertically closest to the point.
+        let closest = glyphs.iter().map(rect).min_by(|a, b| {
+            distance(a.min.y, a.max.y, point.y)
+                .total_cmp(&distance(b.min.y, b.max.y, point.y))
+        });
+        let extent = match closest {
+            Some(rect) => rect.min.y..rect.max.y,
+            None => return 0,
+        };
+
+        // Glyphs with their baseline within the extent are on the same line, also when
+        // their baselines differ slightly, e.g. by mixed scales or font metrics.
+        let mut line: Vec<&SectionGlyph> = glyphs
+            .iter()
+            .filter(|glyph| extent.contains(&glyph.glyph.position.y))
+            .collect();
+        line.sort_by(|a, b| a.glyph.position.x.total_cmp(&b.glyph.position.x));
+        for glyph in &line {
+            let bounds = rect(glyph);
+            if point.x < (bounds.min.x + bounds.max.x) / 2.0 {
+                return start(glyph);
+            }
+        }
+        line.iter().map(|glyph| end(glyph)).max().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the `PositionedGlyph`s of the given section.
+    ///
+    /// With [`YAxis::Up`] (see [`BrushBuilder::with_y_axis()`]) glyph positions are in
+    /// the y-down layout space, with negated y coordinates.
+    #[inline]
+    pub fn glyphs_iter<'a, 'b, S>(&'b mut self, section: S) -> SectionGlyphIter<'b>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let prepared = self.prepare_section(section.into());
+        self.inner.glyphs(prepared.section())
+    }
+
+    /// Returns the number of visual lines of the section after wrapping, using the same
+    /// layout as [`queue`](#method.queue). Empty lines between lines of text are
+    /// counted too.
+    pub fn line_count<'a, S>(&mut self, section: S) -> usize
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let prepared = self.prepare_section(section.into());
+        let lines = self.lines(&prepared.section(), 1.0);
+
+        // Empty lines have no glyphs, count them by the gaps between baselines.
+        let empty: usize = lines
+            .windows(2)
+            .map(|pair| {
+                let advance = pair[1].baseline - pair[0].baseline;
+                let expected = pair[1].ascent - pair[0].descent;
+                let height = pair[1].ascent - pair[1].descent;
+                ((advance - expected) / height).round().max(0.0) as usize
+            })
+            .sum();
+        lines.len() + empty
+    }
+
+    /// Sets the DPI scale factor of the window, the ratio of physical to logical pixels.
+    /// Used by [`Self::to_physical()`] and [`Self::to_logical()`].
+    #[inline]
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Returns the DPI scale factor set with [`Self::set_scale_factor()`] or
+    /// [`BrushBuilder::with_scale_factor()`].
+    #[inline]
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Converts a `logical` point, like a window cursor position, to physical
+    /// pixels. Use it when sections are positioned in physical pixels, so hit testing
+    /// against glyph positions uses consistent coordinates.
+    #[inline]
+    pub fn to_physical(&self, logical: Point) -> Point {
+        point(logical.x * self.scale_factor, logical.y * self.scale_factor)
+    }
+
+    /// Converts a `physical` point, like glyph positions of sections
+    /// positioned in physical pixels, to logical pixels.
+    #[inline]
+    pub fn to_logical(&self, physical: Point) -> Point {
+        point(
+            physical.x / self.scale_factor,
+            physical.y / self.scale_factor,
+        )
+    }
+
+    /// Sets the number of depth layers sections can be placed into with
+    /// [`SectionExt::layer()`](crate::SectionExt::layer), see [`Self::layer_z()`].
+    #[inline]
+    pub fn set_depth_layers(&mut self, count: u32) {
+        self.depth_layers = count.max(1);
+    }
+
+    /// Returns the *z* of the depth `layer`, for drawing other geometry (like UI
+    /// panels) at the same depth as text of the layer.
+    ///
+    /// The layers evenly divide the `(0.0, 1.0)` depth range, layer `0` being the
+    /// furthest and the last layer the closest, as expected by the
+    /// [`wgpu::CompareFunction::Less`] depth test. Layers past the last one share its
+    /// *z*.
+    #[inline]
+    pub fn layer_z(&self, layer: u32) -> f32 {
+        let layer = layer.min(self.depth_layers - 1);
+        (self.depth_layers - layer) as f32 / (self.depth_layers + 1) as f32
+    }
+
+    /// Returns the number of vertices the inner vertex buffer can hold before it has to
+    /// be reallocated, see [`BrushBuilder::with_vertex_capacity()`].
+    #[inline]
+    pub fn vertex_capacity(&self) -> usize {
+        self.pipeline.vertex_capacity()
+    }
+
+    /// Returns the inner vertex buffer holding one vertex instance per glyph or quad,
+    /// e.g. for binding it in a compute pass with the usages added by
+    /// [`BrushBuilder::with_vertex_buffer_usage()`]. The first
+    /// [`FrameStats::instances`] vertices are drawn.
+    ///
+    /// The buffer is replaced by a bigger one when it has to grow (see
+    /// [`Self::vertex_capacity()`]), so bind groups using it should be recreated then.
+    #[inline]
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer {
+        self.pipeline.vertex_buffer()
+    }
+
+    /// Returns the vertex instances of the last [`queue`](#method.queue) (or
+    /// [`Self::upload()`]) call, as they are in the inner vertex buffer: solid quads
+    /// (like line backgrounds) first, glyphs after them.
+    ///
+    /// Useful for drawing the text with a custom pipeline, which can consume them
+    /// directly with [`Vertex::buffer_layout()`], or merging them into another vertex
+    /// stream. Texture coordinates refer to the current cache texture, see
+    /// [`Self::cache_texture()`].
+    #[inline]
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// Returns the styles of [`Self::vertices()`], one per vertex, or an empty slice if
+    /// no vertex is rotated, has a gradient or a transform. Custom pipelines consume
+    /// them from a second vertex buffer with [`VertexStyle::buffer_layout()`].
+    #[inline]
+    pub fn vertex_styles(&self) -> &[VertexStyle] {
+        &self.styles
+    }
+
+    /// Returns the available fonts.
+    ///
+    /// The `FontId` corresponds to the index of the font data.
+    pub fn fonts(&self) -> &[F] {
+        self.inner.fonts()
+    }
+
+    /// Returns the vertical metrics of the font with `font_id` at the `px` scale, e.g.
+    /// for aligning icons and other non-text elements with the text baseline. `None`
+    /// if the brush has no font with the id.
+    pub fn font_metrics(&self, font_id: FontId, px: f32) -> Option<LineMetrics> {
+        let scaled = self.inner.fonts().get(font_id.0)?.as_scaled(px);
+        Some(LineMetrics {
+            ascent: scaled.ascent(),
+            descent: scaled.descent(),
+            line_gap: scaled.line_gap(),
+        })
+    }
+
+    /// Adds a font after the brush was built, like a font loaded by the user at
+    /// runtime, and returns the [`FontId`] sections can use it with.
+    ///
+    /// Ids of the other fonts are unchanged and already cached glyphs stay cached, the
+    /// new font's glyphs are cached when first queued. Only CPU side font data is
+    /// touched, no **wgpu** resources, so fonts can be added on any thread owning the
+    /// brush between [`queue`](#method.queue) calls.
+    pub fn add_font<I: Into<F>>(&mut self, font: I) -> FontId {
+        self.inner.add_font(font)
+    }
+
+    /// Replaces the font with `font_id` by `font`, e.g. for reloading a font from disk
+    /// while designing it.
+    ///
+    /// **glyph_brush** can't invalidate the cached glyphs of a single font, so like
+    /// [`Self::clear_cache()`], all cached glyphs and layouts are dropped (keeping the
+    /// current cache texture dimensions). Queue the text again before the next
+    /// [`draw`](#method.draw), all [`StaticText`] becomes stale.
+    ///
+    /// Fails with [`BrushError::UnknownReplacedFont`] without dropping the cache if
+    /// there is no font with `font_id`.
+    pub fn replace_font<I: Into<F>>(
+        &mut self,
+        font_id: FontId,
+        font: I,
+    ) -> Result<(), BrushError>
+    where
+        F: Clone,
+        H: Clone,
+    {
+        if font_id.0 >= self.inner.fonts().len() {
+            return Err(BrushError::UnknownReplacedFont(font_id));
+        }
+        let mut builder = self.inner.to_builder();
+        builder.font_data[font_id.0] = font.into();
+        self.inner = builder.build();
+        self.cache_generation += 1;
+        if let Some(resident) = &mut self.resident {
+            resident.clear();
+        }
+        if let Some(atlas) = &mut self.color_atlas {
+            atlas.clear();
+        }
+        Ok(())
+    }
+
+    /// Returns the cache texture dimensions `(width, height)`.
+    #[inline]
+    pub fn cache_dimensions(&self) -> (u32, u32) {
+        self.inner.texture_dimensions()
+    }
+
+    /// Drops all cached glyphs and recreates the cache texture with its initial
+    /// dimensions (see [`BrushBuilder::initial_cache_size()`]), reclaiming the space
+    /// of a grown cache, e.g. after a scene transition.
+    ///
+    /// The new cache texture is empty, so queue the text again before the next
+    /// [`draw`](#method.draw), which rasterizes its glyphs again. Sections kept
+    /// resident while trimming is paused ([`Self::set_trimming()`]) are released and
+    /// all [`StaticText`] becomes stale.
+    pub fn clear_cache(&mut self, device: &wgpu::Device) {
+        let (width, height) = self.initial_cache_size;
+        self.inner.resize_texture(width, height);
+        self.pipeline.resize_texture(device, (width, height));
+        self.cache_generation += 1;
+        if let Some(resident) = &mut self.resident {
+            resident.clear();
+        }
+        if let Some(atlas) = &mut self.color_atlas {
+            atlas.clear();
+        }
+    }
+
+    /// Returns the cache texture, a [`wgpu::TextureFormat::R8Unorm`] texture holding one
+    /// coverage byte per texel, e.g. for drawing it onto a debug quad. It's only meant
+    /// for reading, the brush writes cached glyphs into it.
+    ///
+    /// The cache texture is replaced whenever it grows (see
+    /// [`BrushBuilder::cache_growth()`]), so don't keep it (or views of it) across
+    /// [`queue`](#method.queue) calls.
+    #[inline]
+    pub fn cache_texture(&self) -> &wgpu::Texture {
+        self.pipeline.cache_texture()
+    }
+
+    /// Returns glyph cache statistics of the last [`queue`](#method.queue) (or
+    /// [`Self::upload()`]) call, e.g. for deciding when to pre-warm or reset the cache
+    /// in apps cycling through many fonts and sizes.
+    ///
+    /// Frequent uploads of many glyphs mean the cache texture is too small for the
+    /// text of a frame and glyphs keep getting evicted and rasterized again.
+    /// **glyph_brush** doesn't expose which glyphs are resident, so the number of
+    /// cached glyphs and the cache texture occupancy aren't available.
+    #[inline]
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats
+    }
+
+    /// Returns draw statistics of the last [`queue`](#method.queue) (or
+    /// [`Self::upload()`]) call, for profiling.
+    #[inline]
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Creates a view of the whole cache texture, see [`Self::cache_texture()`].
+    #[inline]
+    pub fn cache_texture_view(&self) -> wgpu::TextureView {
+        self.cache_texture()
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Reads the cache texture back into CPU memory, blocking until the GPU copy is
+    /// finished.
+    ///
+    /// Returns one coverage byte per texel, row by row, with rows being
+    /// [`Self::cache_dimensions()`] width bytes long. Meant for tests and debugging
+    /// of the cache texture contents.
+    pub fn read_cache(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("wgpu-text Cache Readback Encoder"),
+            });
+        let readback = self.copy_cache(device, &mut encoder);
+        queue.submit([encoder.finish()]);
+        readback.read(device)
+    }
+
+    /// Reads the cache texture back into CPU memory like [`Self::read_cache()`], but
+    /// without blocking, e.g. for snapshot tests running in an async executor.
+    ///
+    /// The copy is submitted right away, see [`TextureReadback::read_async()`] for when
+    /// the returned future resolves.
+    pub fn read_cache_async(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>, wgpu::BufferAsyncError>> {
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("wgpu-text Cache Readback Encoder"),
+            });
+        let readback = self.copy_cache(device, &mut encoder);
+        queue.submit([encoder.finish()]);
+        readback.read_async()
+    }
+
+    /// Records a copy of the cache texture into the provided `encoder`, for reading
+    /// back cache textures of multiple brushes with a single submission.
+    ///
+    /// Call [`TextureReadback::read()`] once the `encoder` has been submitted.
+    /// See [`Self::read_cache()`].
+    #[inline]
+    pub fn copy_cache(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> TextureReadback {
+        self.pipeline.copy_cache(device, encoder)
+    }
+
+    /// Pauses or resumes trimming of the glyph cache.
+    ///
+    /// Normally, after every [`queue`](#method.queue) call, layouts and cached glyphs of
+    /// sections that weren't queued in that call are trimmed. While trimming is paused,
+    /// every section queued since pausing stays resident, so text that disappears and
+    /// reappears during heavy-churn periods (like transitions) isn't rasterized again.
+    ///
+    /// Resuming trimming releases the resident sections and the next
+    /// [`queue`](#method.queue) call trims the cache once.
+    ///
+    /// Has no effect if `cache_glyph_positioning` is disabled.
+    pub fn set_trimming(&mut self, trimming: bool) {
+        match (trimming, &self.resident) {
+            (true, _) => self.resident = None,
+            (false, None) => self.resident = Some(Vec::new()),
+            (false, Some(_)) => (),
+        }
+    }
+
+    /// Returns `true` if the glyph cache gets trimmed after every
+    /// [`queue`](#method.queue) call. See [`Self::set_trimming()`].
+    #[inline]
+    pub fn trimming(&self) -> bool {
+        self.resident.is_none()
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function.
+    ///
+    /// Drawing only reads the inner vertex buffer, so it can be called any number of
+    /// times, e.g. once per render pass of a frame (main and picking passes), without
+    /// laying out or uploading the text again. The vertex buffer is only rewritten by
+    /// [`queue`](#method.queue) (or [`Self::upload()`]), and only when the queued
+    /// sections changed since the previous call.
+    #[inline]
+    pub fn draw<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw(rpass)
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function clipped to the
+    /// `clip` rectangle in render target pixels, like the viewport of a scroll view.
+    ///
+    /// Unlike section bounds, glyphs are clipped by the scissor test without being
+    /// distorted. The scissor rect is left set on `rpass` and `clip` must lie within
+    /// the render target. Nothing is drawn if `clip` has no area.
+    #[inline]
+    pub fn draw_clipped<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        clip: Rectangle<u32>,
+    ) {
+        let width = clip.max[0].saturating_sub(clip.min[0]);
+        let height = clip.max[1].saturating_sub(clip.min[1]);
+        if width == 0 || height == 0 {
+            return;
+        }
+        rpass.set_scissor_rect(clip.min[0], clip.min[1], width, height);
+        self.pipeline.draw(rpass)
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function with the
+    /// stencil `reference` value, for masking text through the stencil buffer of the
+    /// render pass, like reveal effects.
+    ///
+    /// The stencil test and operations are the ones of the *depth_stencil* state
+    /// provided with [`BrushBuilder::with_depth_stencil()`]. The stencil reference is
+    /// left set on `rpass`.
+    #[inline]
+    pub fn draw_with_stencil<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        reference: u32,
+    ) {
+        rpass.set_stencil_reference(reference);
+        self.pipeline.draw(rpass)
+    }
+
+    /// Queues `sections` and captures their vertices into a [`StaticText`], which can
+    /// be drawn with [`Self::draw_static()`] on later frames without any layout or
+    /// vertex buffer upload, unlike [`queue`](#method.queue) and [`draw`](#method.draw).
+    /// Useful for HUDs with many unchanging labels.
+    ///
+    /// `sections` replace the queued text, so queue again before the next
+    /// [`draw`](#method.draw).
+    pub fn create_static<'a, S>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sections: Vec<S>,
+    ) -> Result<StaticText, BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        self.queue(device, queue, sections)?;
+        let vertices = &self.vertices;
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-text Static Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let styles = (!self.styles.is_empty())
+            .then(|| self.pipeline.create_style_buffer(device, &self.styles));
+
+        Ok(StaticText {
+            buffer,
+            styles,
+            vertices: vertices.len() as u32,
+            generation: self.cache_generation,
+        })
+    }
+
+    /// Returns `true` if the `static_text` can still be drawn. Static text becomes
+    /// stale when its glyphs could have moved in the cache texture, that is whenever
+    /// new glyphs get cached or the cache texture gets resized. Recreate stale static
+    /// text with [`Self::create_static()`].
+    #[inline]
+    pub fn is_static_valid(&self, static_text: &StaticText) -> bool {
+        static_text.generation == self.cache_generation
+    }
+
+    /// Draws the `static_text` created with [`Self::create_static()`]. Stale static text
+    /// (see [`Self::is_static_valid()`]) isn't drawn.
+    #[inline]
+    pub fn draw_static<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        static_text: &'pass StaticText,
+    ) {
+        if self.is_static_valid(static_text) {
+            self.pipeline.draw_buffer(
+                rpass,
+                &static_text.buffer,
+                static_text.styles.as_ref(),
+                static_text.vertices,
+            );
+        }
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function with an outline
+    /// in a single pass, set with [`Self::set_outlined()`].
+    ///
+    /// Glyphs are filled with a single color (replacing the section colors) and the
+    /// outline ring, found by sampling the neighbouring glyph coverage, is blended
+    /// under the fill so the overlap isn't blended twice. Neighbour samples are limited
+    /// to each glyph's own cache texture region, so wide outlines don't pick up
+    /// neighbouring glyphs.
+    #[inline]
+    pub fn draw_outlined<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::Outlined)
+    }
+
+    /// Sets the `fill_color`, `outline_color` and outline `width` in pixels of
+    /// [`Self::draw_outlined()`]. The parameters are written to the `queue` and used by
+    /// all `draw_outlined` calls until they're set again.
+    #[inline]
+    pub fn set_outlined(
+        &mut self,
+        fill_color: [f32; 4],
+        outline_color: [f32; 4],
+        width: f32,
+        queue: &wgpu::Queue,
+    ) {
+        self.pipeline.update_effect(
+            EffectSlot::Outlined,
+            EffectUniform::outlined(fill_color, outline_color, width),
+            queue,
+        );
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function with an outline
+    /// set with [`Self::set_with_outline()`], keeping the section colors, e.g. for
+    /// legible labels over busy backgrounds.
+    ///
+    /// The outline is found the same way as with [`Self::draw_outlined()`], which
+    /// fills all glyphs with a single color instead.
+    #[inline]
+    pub fn draw_with_outline<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::WithOutline)
+    }
+
+    /// Sets the `outline_color` and outline `width` in pixels of
+    /// [`Self::draw_with_outline()`]. The parameters are written to the `queue` and
+    /// used by all `draw_with_outline` calls until they're set again.
+    #[inline]
+    pub fn set_with_outline(
+        &mut self,
+        outline_color: Color,
+        width: f32,
+        queue: &wgpu::Queue,
+    ) {
+        self.pipeline.update_effect(
+            EffectSlot::WithOutline,
+            EffectUniform::with_outline(outline_color, width),
+            queue,
+        );
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function clipped to an
+    /// arbitrary shape, like a rounded rectangle or a circular avatar region, set with
+    /// [`Self::set_masked_by()`].
+    ///
+    /// Text outside of the mask rectangle is clipped entirely. Unlike filling text with
+    /// an image, the text keeps its own colors.
+    #[inline]
+    pub fn draw_masked_by<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::Masked)
+    }
+
+    /// Sets the `mask` of [`Self::draw_masked_by()`]. The alpha channel of `mask` (a
+    /// filterable 2D texture view) is stretched over `mask_rect`, which is given in the
+    /// same coordinates as the section positions, and multiplies the glyph coverage.
+    ///
+    /// The mask is used by all `draw_masked_by` calls until it's set again, independent
+    /// of the fill of [`Self::draw_image_filled()`].
+    #[inline]
+    pub fn set_masked_by(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mask: &wgpu::TextureView,
+        mask_rect: Rect,
+    ) {
+        self.pipeline.set_mask(device, EffectSlot::Masked, mask);
+        self.pipeline.update_effect(
+            EffectSlot::Masked,
+            EffectUniform::masked([
+                mask_rect.min.x,
+                mask_rect.min.y,
+                mask_rect.max.x,
+                mask_rect.max.y,
+            ]),
+            queue,
+        );
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function filled with an
+    /// image set with [`Self::set_image_filled()`] instead of a solid color.
+    #[inline]
+    pub fn draw_image_filled<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::ImageFilled)
+    }
+
+    /// Sets the `fill` of [`Self::draw_image_filled()`]. The `fill` texture view (a
+    /// filterable 2D texture) is stretched over `fill_rect`, given in the same
+    /// coordinates as the section positions. The sampled fill is multiplied by each
+    /// section's color and by `tint`, so the same pattern can be recolored per label.
+    /// Use white section colors and `tint` to keep the image colors.
+    ///
+    /// The fill is used by all `draw_image_filled` calls until it's set again,
+    /// independent of the mask of [`Self::draw_masked_by()`].
+    #[inline]
+    pub fn set_image_filled(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        fill: &wgpu::TextureView,
+        fill_rect: Rect,
+        tint: Color,
+    ) {
+        self.pipeline
+            .set_mask(device, EffectSlot::ImageFilled, fill);
+        self.pipeline.update_effect(
+            EffectSlot::ImageFilled,
+            EffectUniform::image_filled(
+                [
+                    fill_rect.min.x,
+                    fill_rect.min.y,
+                    fill_rect.max.x,
+                    fill_rect.max.y,
+                ],
+                tint,
+            ),
+            queue,
+        );
+    }
+
+    /// Draws all sections queued with [`queue`](#method.queue) function with a
+    /// highlight band sweeping across the text, a shimmer set with
+    /// [`Self::set_shimmer()`].
+    #[inline]
+    pub fn draw_shimmer<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::Shimmer)
+    }
+
+    /// Sets the shimmer of [`Self::draw_shimmer()`]. Glyphs are filled with
+    /// `base_color` (replacing the section colors) blended towards `highlight_color`
+    /// within the band of `width` pixels. The band moves from the left to the right
+    /// edge of all queued text once per unit of `time`, e.g. pass the elapsed seconds
+    /// for one sweep per second.
+    ///
+    /// The band follows the queued text, so only `time` needs to be set again per
+    /// frame.
+    #[inline]
+    pub fn set_shimmer(
+        &mut self,
+        time: f32,
+        base_color: Color,
+        highlight_color: Color,
+        width: f32,
+        queue: &wgpu::Queue,
+    ) {
+        self.pipeline.update_effect(
+            EffectSlot::Shimmer,
+            EffectUniform::shimmer(
+                self.text_bounds(),
+                base_color,
+                highlight_color,
+                time,
+                width,
+            ),
+            queue,
+        );
+    }
+
+    /// Horizontal range of the queued text in section coordinates, as shimmer bounds.
+    fn text_bounds(&self) -> [f32; 4] {
+        let x_scale = if self.subpixel { SUBPIXELS } else { 1.0 };
+        let (min_x, max_x) = Instance::x_range(&self.text_vertices).unwrap_or_default();
+        [min_x / x_scale, 0.0, max_x / x_scale, 1.0]
+    }
+
+    /// Thickens thin glyphs by applying a max filter of `radius` cache texels to the
+    /// glyph coverage when drawing, so hairline fonts stay visible at small sizes
+    /// without changing the font. Unlike scaling the coverage per pixel, the filter
+    /// considers neighbouring texels, which preserves the glyph shapes better.
+    ///
+    /// The filter only samples each glyph's own cache texture region, which includes
+    /// the 1 texel glyph padding, so glyphs never bleed into each other. Radii up to
+    /// about `1.0` work best. Applies to all draw functions. Defaults to `0.0`
+    /// (disabled).
+    #[inline]
+    pub fn set_dilation(&mut self, radius_texels: f32, queue: &wgpu::Queue) {
+        self.pipeline.set_dilation(radius_texels, queue);
+    }
+
+    /// Multiplies the opacity of everything drawn by the brush with `alpha`, clamped to
+    /// `0.0..=1.0`, for cheap fade in and out animations. Unlike changing the section
+    /// colors, the text isn't laid out or uploaded again.
+    ///
+    /// Applies to all draw functions, including [`Self::draw_static()`], except with a
+    /// custom fragment shader ([`BrushBuilder::with_fragment_shader()`]). Defaults to
+    /// `1.0`, drawing text unchanged.
+    #[inline]
+    pub fn set_alpha(&mut self, alpha: f32, queue: &wgpu::Queue) {
+        self.pipeline.set_alpha(alpha, queue);
+    }
+
+    /// Returns the opacity multiplier set with [`Self::set_alpha()`].
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        self.pipeline.alpha()
+    }
+
+    /// Gives text drawn with [`draw`](#method.draw) a raised (or, with a negative
+    /// `strength`, engraved) look by shading glyph edges based on the coverage
+    /// gradient, as if lit from `light_dir`.
+    ///
+    /// `light_dir` points from the text towards the light in text coordinates, e.g.
+    /// `[-1.0, -1.0]` for a light in the top left corner. The gradient is sampled from
+    /// each glyph's own cache texture region only. A `strength` of `0.0` disables
+    /// embossing, which is the default.
+    #[inline]
+    pub fn set_emboss(
+        &mut self,
+        strength: f32,
+        light_dir: [f32; 2],
+        queue: &wgpu::Queue,
+    ) {
+        self.pipeline.set_emboss(strength, light_dir, queue);
+    }
+
+    /// Returns the coverage dilation radius set with [`Self::set_dilation()`].
+    #[inline]
+    pub fn dilation(&self) -> f32 {
+        self.pipeline.dilation()
+    }
+
+    /// Draws only the outline of all sections queued with [`queue`](#method.queue)
+    /// function, hollow text with a transparent interior, set with
+    /// [`Self::set_hollow()`].
+    ///
+    /// The outline ring is found the same way as with [`Self::draw_outlined()`].
+    /// Strokes narrower than the glyph coverage transition stay hollow too, they're
+    /// just surrounded by the ring.
+    #[inline]
+    pub fn draw_hollow<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
+        self.pipeline.draw_effect(rpass, EffectSlot::Hollow)
+    }
+
+    /// Sets the `outline_color` and outline `width` in pixels of
+    /// [`Self::draw_hollow()`]. The parameters are written to the `queue` and used by
+    /// all `draw_hollow` calls until they're set again.
+    #[inline]
+    pub fn set_hollow(&mut self, outline_color: Color, width: f32, queue: &wgpu::Queue) {
+        self.pipeline.update_effect(
+            EffectSlot::Hollow,
+            EffectUniform::hollow(outline_color, width),
+            queue,
+        );
+    }
+
+    /// Queues `sections` and renders them into `view` of the given `size`, using an
+    /// orthographic matrix of that size. The render pass loads the previous `view`
+    /// contents, text is drawn on top of it.
+    ///
+    /// The brush's render format must match the format of `view`. Render pass has no
+    /// depth attachment, so the brush shouldn't be built with a *depth_stencil*.
+    ///
+    /// The view matrix used for other drawing is restored afterwards, but
+    /// `sections` replace the queued text, so queue again before the next
+    /// [`draw`](#method.draw).
+    pub fn render_to_texture<'a, S>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &wgpu::TextureView,
+        size: (u32, u32),
+        sections: Vec<S>,
+    ) -> Result<(), BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let (matrix, transform) = (self.view_matrix, self.transform.take());
+        let ortho = self.y_axis.ortho(size.0 as f32, size.1 as f32);
+        self.update_matrix(ortho, queue);
+        let queued = self.queue(device, queue, sections);
+        self.transform = transform;
+        if let Err(err) = queued {
+            self.update_matrix(matrix, queue);
+            return Err(err);
+        }
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("wgpu-text Render To Texture Encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("wgpu-text Render To Texture Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            self.pipeline.draw(&mut rpass);
+        }
+        queue.submit([encoder.finish()]);
+
+        self.update_matrix(matrix, queue);
+        Ok(())
+    }
+
+    /// Renders `sections` into a single `layer` of a `D2Array` texture, for example
+    /// a page of a paged UI. See [`Self::render_to_texture()`].
+    ///
+    /// The orthographic matrix is sized to one layer (page) of the `texture`.
+    pub fn render_to_layer<'a, S>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        layer: u32,
+        sections: Vec<S>,
+    ) -> Result<(), BrushError>
+    where
+        S: Into<StyledSection<'a>>,
+    {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("wgpu-text Texture Layer View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        });
+        let size = (texture.width(), texture.height());
+        self.render_to_texture(device, queue, &view, size, sections)
+    }
+
+    /// Changes the cache texture sampler filters for magnification (`mag`) and
+    /// minification (`min`), see [`BrushBuilder::with_sampler_filters()`].
+    ///
+    /// The sampler and its bind group are recreated, unless the filters are unchanged.
+    #[inline]
+    pub fn set_sampler_filters(
+        &mut self,
+        device: &wgpu::Device,
+        mag: wgpu::FilterMode,
+        min: wgpu::FilterMode,
+    ) {
+        self.pipeline
+            .set_sampler_filters(device, SamplerFilters { mag, min });
+    }
+
+    /// Returns the cache texture sampler filters as `(mag, min)`.
+    #[inline]
+    pub fn sampler_filters(&self) -> (wgpu::FilterMode, wgpu::FilterMode) {
+        let SamplerFilters { mag, min } = self.pipeline.sampler_filters();
+        (mag, min)
+    }
+
+    /// Returns the blend mode currently used for drawing.
+    #[inline]
+    pub fn blend_mode(&self) -> BlendMode {
+        self.pipeline.blend_mode()
+    }
+
+    /// Changes the blend mode used for drawing, e.g. switching to
+    /// [`BlendMode::Additive`] for a glow on hover.
+    ///
+    /// The inner render pipeline for a blend mode is created the first time the mode is
+    /// used and kept for later switches, so toggling between modes is cheap.
+    #[inline]
+    pub fn set_blend_mode(&mut self, device: &wgpu::Device, blend_mode: BlendMode) {
+        self.pipeline.set_blend_mode(device, blend_mode);
+    }
+
+    /// Resizes the view matrix. Updates the default orthographic view matrix with
+    /// provided dimensions and uses it for rendering.
+    ///
+    /// Run this function whenever the surface config is resized.
+    /// **Surface** dimensions are most commonly *width* and *height*.
+    ///
+    /// It isn't tied to any window, when drawing into an off-screen texture pass the
+    /// texture dimensions instead, e.g. before drawing into it and again before
+    /// drawing onto the surface. The matrix is set with [`Self::update_matrix()`], so
+    /// the transform set with [`Self::set_transform()`] still applies.
+    ///
+    /// The last computed matrix is reused when called with unchanged dimensions and
+    /// nothing gets written if the render matrix doesn't change, so calling this
+    /// defensively, e.g. every frame, is cheap.
+    ///
+    /// **Matrix**:
+    /// ```rust
+    /// pub fn ortho(width: f32, height: f32) -> [[f32; 4]; 4] {
+    ///     [
+    ///         [2.0 / width, 0.0,          0.0, 0.0],
+    ///         [0.0,        -2.0 / height, 0.0, 0.0],
+    ///         [0.0,         0.0,          1.0, 0.0],
+    ///         [-1.0,        1.0,          0.0, 1.0]
+    ///     ]
+    /// }
+    /// ```
+    #[inline]
+    pub fn resize_view(&mut self, width: f32, height: f32, queue: &wgpu::Queue) {
+        let matrix = match self.ortho {
+            Some((size, matrix)) if size == (width, height) => matrix,
+            _ => {
+                let matrix = self.y_axis.ortho(width, height);
+                self.ortho = Some(((width, height), matrix));
+                matrix
+            }
+        };
+        self.update_matrix(matrix, queue);
+    }
+
+    /// Resizes the view. Updates text rendering matrix with the provided one.
+    ///
+    /// Use [`Self::resize_view()`] to update and replace the current render matrix
+    /// with a default orthographic matrix.
+    ///
+    /// Feel free to use [`ortho()`] to create more complex matrices by yourself.
+    ///
+    /// The transform set with [`Self::set_transform()`] is still applied before the
+    /// provided matrix. Nothing gets written if the resulting matrix equals the current
+    /// one.
+    #[inline]
+    pub fn update_matrix<M>(&mut self, matrix: M, queue: &wgpu::Queue)
+    where
+        M: Into<Matrix>,
+    {
+        self.view_matrix = matrix.into();
+        let matrix = match self.transform {
+            Some(transform) => mul_matrix(self.view_matrix, transform),
+            None => self.view_matrix,
+        };
+        self.pipeline
+            .update_matrix(layout_matrix(matrix, self.y_axis), queue);
+    }
+
+    /// Sets a `transform` matrix applied to text positions before the view matrix
+    /// (the orthographic one of [`Self::resize_view()`] or the one provided with
+    /// [`Self::update_matrix()`]), like a camera or a scene transform.
+    ///
+    /// The transform survives view resizes, [`Self::resize_view()`] only updates the
+    /// view part. [`Self::render_to_texture()`] ignores it.
+    #[inline]
+    pub fn set_transform<M>(&mut self, transform: M, queue: &wgpu::Queue)
+    where
+        M: Into<Matrix>,
+    {
+        self.transform = Some(transform.into());
+        self.update_matrix(self.view_matrix, queue);
+    }
+
+    /// Removes the transform set with [`Self::set_transform()`].
+    #[inline]
+    pub fn reset_transform(&mut self, queue: &wgpu::Queue) {
+        self.transform = None;
+        self.update_matrix(self.view_matrix, queue);
+    }
+
+    /// Returns the transform set with [`Self::set_transform()`], if any.
+    #[inline]
+    pub fn transform(&self) -> Option<Matrix> {
+        self.transform
+    }
+
+    /// Converts a y coordinate between the brush [`YAxis`] and the y-down layout space
+    /// of **glyph_brush**, where [`YAxis::Up`] coordinates are negated.
+    #[inline]
+    fn flip_y(&self, y: f32) -> f32 {
+        match self.y_axis {
+            YAxis::Down => y,
+            YAxis::Up => -y,
+        }
+    }
+
+    /// Converts a rectangle between the brush [`YAxis`] and the layout space, see
+    /// [`Self::flip_y()`].
+    #[inline]
+    fn flip_rect(&self, rect: Rect) -> Rect {
+        match self.y_axis {
+            YAxis::Down => rect,
+            YAxis::Up => Rect {
+                min: point(rect.min.x, -rect.max.y),
+                max: point(rect.max.x, -rect.min.y),
+            },
+        }
+    }
+
+    /// Applies the section options and brush-wide section adjustments (like control
+    /// character sanitizing) so that queuing and measuring operate on the same layout.
+    fn prepare_section<'a>(&self, section: StyledSection<'a>) -> PreparedSection<'a> {
+        let layer = section.layer;
+        let keep_tabs = section.tab_width > 0.0;
+        let mut section = section.resolve();
+        if self.y_axis == YAxis::Up {
+            let position = &mut section.to_mut().screen_position;
+            position.1 = -position.1;
+        }
+        if let Some(layer) = layer {
+            let z = self.layer_z(layer);
+            for text in &mut section.to_mut().text {
+                text.extra.z = z;
+            }
+        }
+
+        let mut owned = None;
+        if self.sanitize_control_chars {
+            let sanitized: Vec<_> = section
+                .text
+                .iter()
+                .map(|t| sanitize_text(t.text, keep_tabs))
+                .collect();
+            if sanitized.iter().any(Option::is_some) {
+                let mut prepared = section.as_ref().to_owned();
+                for (text, sanitized) in prepared.text.iter_mut().zip(sanitized) {
+                    if let Some(sanitized) = sanitized {
+                        text.text = sanitized;
+                    }
+                }
+                owned = Some(prepared);
+            }
+        }
+
+        if !self.fallback_fonts.is_empty() {
+            let missing = |text: &str, font_id: FontId| {
+                text.chars()
+                    .any(|c| self.fallback_font(font_id, c) != font_id)
+            };
+            let needed = match &owned {
+                Some(owned) => owned.text.iter().any(|t| missing(&t.text, t.font_id)),
+                None => section.text.iter().any(|t| missing(t.text, t.font_id)),
+            };
+            if needed {
+                let mut fallback = owned.unwrap_or_else(|| section.as_ref().to_owned());
+                fallback.text = self.fallback_texts(fallback.text);
+                owned = Some(fallback);
+            }
+        }
+
+        match owned {
+            Some(owned) => PreparedSection::Owned(owned),
+            None => PreparedSection::Section(section),
+        }
+    }
+
+    /// Returns the font the character `c` of a text in the font `font_id` is drawn
+    /// with, the first fallback font having it if the text font doesn't.
+    fn fallback_font(&self, font_id: FontId, c: char) -> FontId {
+        let fonts = self.inner.fonts();
+        let has = |font_id: FontId| fonts[font_id.0].glyph_id(c).0 != 0;
+        if c.is_control() || has(font_id) {
+            return font_id;
+        }
+        self.fallback_fonts
+            .iter()
+            .copied()
+            .find(|&id| has(id))
+            .unwrap_or(font_id)
+    }
+
+    /// Splits the `texts` into runs of characters drawn with the same font, see
+    /// [`BrushBuilder::with_fallback_fonts()`].
+    fn fallback_texts(&self, texts: Vec<OwnedText>) -> Vec<OwnedText> {
+        let mut runs: Vec<OwnedText> = Vec::with_capacity(texts.len());
+        for text in texts {
+            if text.text.is_empty() {
+                runs.push(text);
+                continue;
+            }
+            let mut run: Option<OwnedText> = None;
+            for c in text.text.chars() {
+                let font_id = self.fallback_font(text.font_id, c);
+                match &mut run {
+                    Some(run) if run.font_id == font_id => run.text.push(c),
+                    _ => {
+                        runs.extend(run.take());
+                        run = Some(OwnedText {
+                            text: c.to_string(),
+                            font_id,
+                            scale: text.scale,
+                            extra: text.extra,
+                        });
+                    }
+                }
+            }
+            runs.extend(run);
+        }
+        runs
+    }
+
+    /// Queues the `section` glyphs as pre-positioned glyphs with the `adjustments`
+    /// applied.
+    fn queue_glyphs(
+        &mut self,
+        section: &Section<GlyphExtra>,
+        adjustments: GlyphAdjustments,
+    ) {
+        let mut glyphs: Vec<SectionGlyph> = self
+            .inner
+            .glyphs(section)
+            .filter(|g| {
+                !adjustments.hide_zero_width_spaces
+                    || !section.text[g.section_index].text[g.byte_index..]
+                        .starts_with(ZERO_WIDTH_SPACE)
+            })
+            .cloned()
+            .collect();
+        if adjustments.tabular_figures {
+            self.tabular_figures(section, &mut glyphs);
+        }
+        if adjustments.tab_width > 0.0 {
+            self.tab_stops(section, &mut glyphs, adjustments.tab_width);
+        }
+        if adjustments.letter_spacing != 0.0 {
+            letter_spacing_glyphs(section, &mut glyphs, adjustments.letter_spacing);
+        }
+        if adjustments.line_height != 1.0 {
+            line_height_glyphs(&mut glyphs, adjustments.line_height);
+        }
+        if adjustments.layout_mode == LayoutMode::Vertical {
+            self.vertical_layout(section, &mut glyphs);
+        }
+        let revealed = glyphs.len() as f32 * adjustments.reveal;
+        let count = revealed.ceil() as usize;
+        glyphs.truncate(count);
+
+        if adjustments.run_align != VerticalRunAlign::Baseline {
+            self.align_runs(&mut glyphs, adjustments.run_align);
+        }
+        for glyph in &mut glyphs {
+            let position = &mut glyph.glyph.position;
+            match adjustments.hinting {
+                Hinting::None => (),
+                Hinting::Vertical => position.y = position.y.round(),
+                Hinting::Full => {
+                    *position = point(position.x.round(), position.y.round())
+                }
+            }
+        }
+
+        let fonts = self.inner.fonts();
+        let glyph_bounds = |glyph: &SectionGlyph| {
+            let font = fonts[glyph.font_id.0].as_scaled(glyph.glyph.scale);
+            font.glyph_bounds(&glyph.glyph)
+        };
+        // Extent of all glyphs along the gradient direction.
+        let gradient_extent = adjustments.gradient.and_then(|(_, direction)| {
+            glyphs
+                .iter()
+                .map(|glyph| direction.range(glyph_bounds(glyph)))
+                .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+        });
+        let transform = adjustments
+            .transform
+            .map(|transform| transform.vertex_data(section.screen_position));
+        let mut extra: Vec<GlyphExtra> = glyphs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, glyph)| {
+                let mut extra = section.text[glyph.section_index].extra;
+                let mut opacity = 1.0;
+                if i + 1 == count && revealed.fract() > 0.0 {
+                    opacity *= revealed.fract();
+                }
+                if adjustments.edge_fades.iter().any(Option::is_some) {
+                    let bounds = glyph_bounds(glyph);
+                    let (x, y) = adjustments.local_origin.unwrap_or_default();
+                    let center = (
+                        x + (bounds.min.x + bounds.max.x) / 2.0,
+                        y + (bounds.min.y + bounds.max.y) / 2.0,
+                    );
+                    for (edge, falloff) in adjustments.edge_fades.iter().flatten() {
+                        opacity *= edge.opacity(center, *falloff);
+                    }
+                }
+                if let (Some((end, direction)), Some((min, max))) =
+                    (adjustments.gradient, gradient_extent)
+                {
+                    let (start, stop) = direction.range(glyph_bounds(glyph));
+                    let t = |v: f32| (v - min) / (max - min).max(f32::EPSILON);
+                    let base = extra.color;
+                    extra.color = lerp_color(base, end, t(start));
+                    extra.color_end = lerp_color(base, end, t(stop));
+                    extra.gradient = direction;
+                }
+                extra.color[3] *= opacity;
+                extra.color_end[3] *= opacity;
+                if let Some(jitter) = adjustments.jitter {
+                    let ((x, y), rotation) = jitter.perturbation(i);
+                    glyph.glyph.position.x += x;
+                    glyph.glyph.position.y += y;
+                    extra.rotation = rotation;
+                }
+                if let Some((matrix, translation)) = transform {
+                    extra.transform = matrix;
+                    extra.translation = translation;
+                }
+                if let Some((x, y)) = adjustments.local_origin {
+                    extra.translation[0] += x;
+                    extra.translation[1] += y;
+                }
+                extra
+            })
+            .collect();
+
+        if let Some(((x, y), color)) = adjustments.shadow {
+            // Shadow glyphs are queued first, so they're drawn under the text.
+            let mut shadow = glyphs.clone();
+            for glyph in &mut shadow {
+                glyph.glyph.position.x += x;
+                glyph.glyph.position.y += y;
+            }
+            let mut shadow_extra: Vec<GlyphExtra> = extra
+                .iter()
+                .map(|extra| {
+                    let color = [color[0], color[1], color[2], color[3] * extra.color[3]];
+                    GlyphExtra {
+                        color,
+                        color_end: color,
+                        ..*extra
+                    }
+                })
+                .collect();
+            shadow.append(&mut glyphs);
+            shadow_extra.append(&mut extra);
+            glyphs = shadow;
+            extra = shadow_extra;
+        }
+
+        let mut bounds = adjustments.clip.unwrap_or_else(|| {
+            section.layout.bounds_rect(&SectionGeometry::from(section))
+        });
+        if adjustments.subpixel {
+            for glyph in &mut glyphs {
+                glyph.glyph.scale.x *= SUBPIXELS;
+                glyph.glyph.position.x *= SUBPIXELS;
+            }
+            bounds.min.x *= SUBPIXELS;
+            bounds.max.x *= SUBPIXELS;
+        }
+        if adjustments.color_glyphs {
+            self.queue_color_glyphs(&mut glyphs, &mut extra, bounds);
+        }
+        self.inner.queue_pre_positioned(glyphs, extra, bounds);
+    }
+
+    /// Moves the glyphs with a color image (see [`BrushBuilder::with_color_emoji()`])
+    /// or a bitmap strike of their size (see [`BrushBuilder::prefer_bitmap_strikes()`])
+    /// out of `glyphs` into vertices sampling the color glyph texture, clipped to the
+    /// `bounds`. Glyph coordinates are already supersampled for subpixel rendering.
+    fn queue_color_glyphs(
+        &mut self,
+        glyphs: &mut Vec<SectionGlyph>,
+        extra: &mut Vec<GlyphExtra>,
+        bounds: Rect,
+    ) {
+        let atlas = match &mut self.color_atlas {
+            Some(atlas) => atlas,
+            None => return,
+        };
+        let fonts = self.inner.fonts();
+        let x_scale = if self.subpixel { SUBPIXELS } else { 1.0 };
+
+        let mut kept = Vec::with_capacity(glyphs.len());
+        let mut kept_extra = Vec::with_capacity(extra.len());
+        for (glyph, glyph_extra) in glyphs.drain(..).zip(extra.drain(..)) {
+            let font = &fonts[glyph.font_id.0];
+            let scale = glyph.glyph.scale.y;
+            let color = match atlas.get(font, glyph.font_id, glyph.glyph.id, scale) {
+                Some(color) => color,
+                None => {
+                    kept.push(glyph);
+                    kept_extra.push(glyph_extra);
+                    continue;
+                }
+            };
+            let factor = scale / color.pixels_per_em;
+            // Strikes drawn at their own size stay pixel-perfect on whole pixels.
+            let position = match factor == 1.0 {
+                true => point(
+                    (glyph.glyph.position.x / x_scale).round() * x_scale,
+                    glyph.glyph.position.y.round(),
+                ),
+                false => glyph.glyph.position,
+            };
+            let pixel_coords = Rect {
+                min: point(
+                    position.x + color.rect.min.x * factor * x_scale,
+                    position.y + color.rect.min.y * factor,
+                ),
+                max: point(
+                    position.x + color.rect.max.x * factor * x_scale,
+                    position.y + color.rect.max.y * factor,
+                ),
+            };
+            // Image colors are kept, only the text alpha applies. Grayscale strikes
+            // are white and take the text color.
+            let extra = match color.colored {
+                true => GlyphExtra {
+                    color: [1.0, 1.0, 1.0, glyph_extra.color[3]],
+                    color_end: [1.0, 1.0, 1.0, glyph_extra.color_end[3]],
+                    ..glyph_extra
+                },
+                false => glyph_extra,
+            };
+            self.color_vertices.push(Instance::to_vertex(GlyphVertex {
+                tex_coords: color.tex_coords,
+                pixel_coords,
+                bounds,
+                extra: &extra,
+            }));
+        }
+        *glyphs = kept;
+        *extra = kept_extra;
+    }
+
+    /// Pushes a solid quad per visual line of the `section`, cycling through `colors`.
+    fn line_background_quads(
+        &mut self,
+        section: &Section<GlyphExtra>,
+        colors: &[Color],
+        line_height: f32,
+        quads: &mut Vec<Instance>,
+    ) {
+        let bounds = section.layout.bounds_rect(&SectionGeometry::from(section));
+        let z = section.text.first().map_or(0.0, |t| t.extra.z);
+
+        let lines = self.lines(section, line_height);
+
+        for (i, line) in lines.into_iter().enumerate() {
+            let (min_x, max_x) =
+                match bounds.min.x.is_finite() && bounds.max.x.is_finite() {
+                    true => (bounds.min.x, bounds.max.x),
+                    false => (line.min_x, line.max_x),
+                };
+            let x_scale = if self.subpixel { SUBPIXELS } else { 1.0 };
+            let rect = Rect {
+                min: point(min_x * x_scale, line.baseline - line.ascent),
+                max: point(max_x * x_scale, line.baseline - line.descent),
+            };
+            quads.push(Instance::solid(rect, z, colors[i % colors.len()]));
+        }
+    }
+
+    /// Pushes a solid quad per visual line of the `section` for each of the
+    /// `(underline, strikethrough)` colors which is set.
+    fn decoration_quads(
+        &mut self,
+        section: &Section<GlyphExtra>,
+        (underline, strikethrough): (Option<Color>, Option<Color>),
+        line_height: f32,
+        quads: &mut Vec<Instance>,
+    ) {
+        let z = section.text.first().map_or(0.0, |t| t.extra.z);
+        let x_scale = if self.subpixel { SUBPIXELS } else { 1.0 };
+
+        for line in self.lines(section, line_height) {
+            let thickness = (line.ascent / 12.0).max(1.0);
+            // Top edges, below the baseline and centered at half of the x-height.
+            let strikethrough_y = line.baseline - line.ascent * 0.3 - thickness / 2.0;
+            let decorations = [
+                (underline, line.baseline + thickness),
+                (strikethrough, strikethrough_y),
+            ];
+            for (color, y) in decorations {
+                if let Some(color) = color {
+                    let rect = Rect {
+                        min: point(line.min_x * x_scale, y),
+                        max: point(line.max_x * x_scale, y + thickness),
+                    };
+                    quads.push(Instance::solid(rect, z, color));
+                }
+            }
+        }
+    }
+
+    /// Returns the visual lines of the `section` which contain glyphs, sorted from top
+    /// to bottom, with the advance between them multiplied by `line_height`.
+    fn lines(&mut self, section: &Section<GlyphExtra>, line_height: f32) -> Vec<Line> {
+        let glyphs: Vec<SectionGlyph> = self.inner.glyphs(section).cloned().collect();
+        let mut lines: Vec<Line> = Vec::new();
+        for glyph in glyphs {
+            let font = &self.inner.fonts()[glyph.font_id.0];
+            let scaled = font.as_scaled(glyph.glyph.scale);
+            let (y, x) = (glyph.glyph.position.y, glyph.glyph.position.x);
+            let advance = x + scaled.h_advance(glyph.glyph.id);
+            let descent = scaled.descent() - scaled.line_gap();
+            match lines.iter_mut().find(|line| line.baseline == y) {
+                Some(line) => {
+                    line.ascent = line.ascent.max(scaled.ascent());
+                    line.descent = line.descent.min(descent);
+                    line.min_x = line.min_x.min(x);
+                    line.max_x = line.max_x.max(advance);
+                }
+                None => lines.push(Line {
+                    baseline: y,
+                    ascent: scaled.ascent(),
+                    descent,
+                    min_x: x,
+                    max_x: advance,
+                }),
+            }
+        }
+        lines.sort_by(|a, b| a.baseline.total_cmp(&b.baseline));
+        if let Some(first) = lines.first().map(|line| line.baseline) {
+            for line in &mut lines {
+                line.baseline = first + (line.baseline - first) * line_height;
+            }
+        }
+        lines
+    }
+
+    /// Lays out the `section` from scratch, bypassing the inner layout cache.
+    fn layout<'a>(
+        &self,
+        section: StyledSection<'a>,
+    ) -> (PreparedSection<'a>, Vec<SectionGlyph>) {
+        let tabular_figures = section.tabular_figures;
+        let letter_spacing = section.letter_spacing;
+        let tab_width = section.tab_width;
+        let line_height = section.line_height;
+        let layout_mode = section.layout_mode;
+        let prepared = self.prepare_section(section);
+        let glyphs = {
+            let section = prepared.section();
+            let mut glyphs = section.layout.calculate_glyphs(
+                self.inner.fonts(),
+                &SectionGeometry::from(&section),
+                &section.text[..],
+            );
+            if tabular_figures {
+                self.tabular_figures(&section, &mut glyphs);
+            }
+            if tab_width > 0.0 {
+                self.tab_stops(&section, &mut glyphs, tab_width);
+            }
+            if letter_spacing != 0.0 {
+                letter_spacing_glyphs(&section, &mut glyphs, letter_spacing);
+            }
+            if line_height != 1.0 {
+                line_height_glyphs(&mut glyphs, line_height);
+            }
+            if layout_mode == LayoutMode::Vertical {
+                self.vertical_layout(&section, &mut glyphs);
+            }
+            glyphs
+        };
+        (prepared, glyphs)
+    }
+
+    /// Returns the bounding box of the `glyphs` metrics within the `section` bounds, or
+    /// the `clip` rectangle replacing them, if it has any area.
+    fn bounds(
+        &self,
+        section: &Section<GlyphExtra>,
+        glyphs: &[SectionGlyph],
+        clip: Option<Rect>,
+    ) -> Option<Rect> {
+        let fonts = self.inner.fonts();
+        let bounds = glyphs
+            .iter()
+            .map(|g| {
+                fonts[g.font_id.0]
+                    .as_scaled(g.glyph.scale)
+                    .glyph_bounds(&g.glyph)
+            })
+            .reduce(|a, b| Rect {
+                min: point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                max: point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+            })?;
+        let layout_bounds = clip.unwrap_or_else(|| {
+            section.layout.bounds_rect(&SectionGeometry::from(section))
+        });
+        let bounds = Rect {
+            min: point(
+                bounds.min.x.max(layout_bounds.min.x),
+                bounds.min.y.max(layout_bounds.min.y),
+            ),
+            max: point(
+                bounds.max.x.min(layout_bounds.max.x),
+                bounds.max.y.min(layout_bounds.max.y),
+            ),
+        };
+        (bounds.width() > 0.0 && bounds.height() > 0.0).then_some(bounds)
+    }
+
+    /// Gives all digits of the `glyphs` the advance of the widest digit of their font,
+    /// centering them within it and shifting the following glyphs of the line.
+    fn tabular_figures(
+        &self,
+        section: &Section<GlyphExtra>,
+        glyphs: &mut [SectionGlyph],
+    ) {
+        let fonts = self.inner.fonts();
+        let mut line = None;
+        let mut shift = 0.0;
+        for glyph in glyphs.iter_mut() {
+            if line != Some(glyph.glyph.position.y) {
+                line = Some(glyph.glyph.position.y);
+                shift = 0.0;
+            }
+            glyph.glyph.position.x += shift;
+
+            let text = section.text[glyph.section_index].text;
+            if text[glyph.byte_index..].starts_with(|c: char| c.is_ascii_digit()) {
+                let font = &fonts[glyph.font_id.0];
+                let scaled = font.as_scaled(glyph.glyph.scale);
+                let tabular = ('0'..='9')
+                    .map(|digit| scaled.h_advance(font.glyph_id(digit)))
+                    .fold(0.0, f32::max);
+                let extra = tabular - scaled.h_advance(glyph.glyph.id);
+                glyph.glyph.position.x += extra / 2.0;
+                shift += extra;
+            }
+        }
+    }
+
+    /// Moves the glyphs following each tab of the `section` to the next tab stop, every
+    /// `width` pixels from the section position, and removes the tab glyphs.
+    fn tab_stops(
+        &self,
+        section: &Section<GlyphExtra>,
+        glyphs: &mut Vec<SectionGlyph>,
+        width: f32,
+    ) {
+        let fonts = self.inner.fonts();
+        let mut line = None;
+        let mut shift = 0.0;
+        glyphs.retain_mut(|glyph| {
+            if line != Some(glyph.glyph.position.y) {
+                line = Some(glyph.glyph.position.y);
+                shift = 0.0;
+            }
+            glyph.glyph.position.x += shift;
+
+            let text = section.text[glyph.section_index].text;
+            if !text[glyph.byte_index..].starts_with('\t') {
+                return true;
+            }
+            let advance = fonts[glyph.font_id.0]
+                .as_scaled(glyph.glyph.scale)
+                .h_advance(glyph.glyph.id);
+            let pen = glyph.glyph.position.x - section.screen_position.0;
+            let stop = ((pen / width).floor() + 1.0) * width;
+            shift += stop - pen - advance;
+            false
+        });
+    }
+
+    /// Restacks the laid out `glyphs` of each line of the `section` into a column,
+    /// see [`LayoutMode::Vertical`].
+    fn vertical_layout(
+        &self,
+        section: &Section<GlyphExtra>,
+        glyphs: &mut [SectionGlyph],
+    ) {
+        let fonts = self.inner.fonts();
+        let (mut right, top) = section.screen_position;
+        let mut start = 0;
+        while start < glyphs.len() {
+            let y = glyphs[start].glyph.position.y;
+            let len = glyphs[start..]
+                .iter()
+                .take_while(|glyph| glyph.glyph.position.y == y)
+                .count();
+            let column = &mut glyphs[start..start + len];
+            let width = column
+                .iter()
+                .map(|glyph| fonts[glyph.font_id.0].as_scaled(glyph.glyph.scale).height())
+                .fold(0.0, f32::max);
+            let center = right - width / 2.0;
+            let mut pen = top;
+            for glyph in column {
+                let scaled = fonts[glyph.font_id.0].as_scaled(glyph.glyph.scale);
+                let x = center - scaled.h_advance(glyph.glyph.id) / 2.0;
+                glyph.glyph.position = point(x, pen + scaled.ascent());
+                pen += match scaled.v_advance(glyph.glyph.id) {
+                    advance if advance > 0.0 => advance,
+                    _ => scaled.height(),
+                };
+            }
+            right -= width;
+            start += len;
+        }
+    }
+
+    /// Moves glyphs of each line vertically so that they're aligned by `align` with the
+    /// largest scaled glyph of the line.
+    fn align_runs(&self, glyphs: &mut [SectionGlyph], align: VerticalRunAlign) {
+        let fonts = self.inner.fonts();
+        let metric = |glyph: &SectionGlyph| {
+            let font = &fonts[glyph.font_id.0];
+            let scaled = font.as_scaled(glyph.glyph.scale);
+            match align {
+                VerticalRunAlign::Baseline => 0.0,
+                VerticalRunAlign::CapHeight => font
+                    .outline_glyph(font.glyph_id('H').with_scale(glyph.glyph.scale))
+                    .map(|outline| -outline.px_bounds().min.y)
+                    .unwrap_or_else(|| scaled.ascent()),
+                VerticalRunAlign::Center => (scaled.ascent() + scaled.descent()) / 2.0,
+            }
+        };
+
+        // Lines as (baseline, largest scale, metric of the largest scale).
+        let mut lines: Vec<(f32, f32, f32)> = Vec::new();
+        for glyph in glyphs.iter() {
+            let (y, scale) = (glyph.glyph.position.y, glyph.glyph.scale.y);
+            match lines.iter_mut().find(|line| line.0 == y) {
+                Some(line) if line.1 < scale => *line = (y, scale, metric(glyph)),
+                Some(_) => (),
+                None => lines.push((y, scale, metric(glyph))),
+            }
+        }
+
+        for glyph in glyphs.iter_mut() {
+            let y = glyph.glyph.position.y;
+            if let Some(line) = lines.iter().find(|line| line.0 == y) {
+                glyph.glyph.position.y += metric(glyph) - line.2;
+            }
+        }
+    }
+}
+
+/// Captured vertices of text drawn with [`TextBrush::draw_static()`].
+///
+/// Created with [`TextBrush::create_static()`], the text is forgotten by dropping it.
+#[derive(Debug)]
+pub struct StaticText {
+    buffer: wgpu::Buffer,
+    styles: Option<wgpu::Buffer>,
+    vertices: u32,
+    generation: u64,
+}
+
+/// Text laid out by [`TextBrush::prepare()`], waiting to be uploaded with
+/// [`TextBrush::upload()`].
+#[derive(Debug, Clone)]
+pub struct PreparedText {
+    /// New glyph vertices, `None` if they're unchanged.
+    vertices: Option<Vec<Instance>>,
+    quads: Vec<Instance>,
+    texture_updates: Vec<(Rectangle<u32>, Vec<u8>)>,
+    color_updates: Vec<(Rectangle<u32>, Vec<u8>)>,
+    /// New cache texture size if it had to grow.
+    cache_size: Option<(u32, u32)>,
+}
+
+/// Draw statistics, see [`TextBrush::last_frame_stats()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// Number of glyph (and quad) instances drawn by the next [`TextBrush::draw()`].
+    pub instances: u32,
+    /// `true` if the last upload had to grow (recreate) the cache texture.
+    pub cache_resized: bool,
+    /// Number of vertices written into the vertex buffer by the last upload, only the
+    /// range that changed since the previous one, `0` if the text was unchanged.
+    pub vertices_uploaded: usize,
+}
+
+/// Vertical font metrics in pixels, see [`TextBrush::font_metrics()`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineMetrics {
+    /// Distance from the baseline to the top of the tallest glyphs, positive.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the lowest glyphs, usually
+    /// negative.
+    pub descent: f32,
+    /// Extra space between the descent of a line and the ascent of the next one.
+    pub line_gap: f32,
+}
+
+/// Glyph cache statistics, see [`TextBrush::cache_stats()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Current cache texture dimensions.
+    pub texture_dimensions: (u32, u32),
+    /// Number of glyphs rasterized and uploaded into the cache texture by the last
+    /// upload.
+    pub uploaded_glyphs: usize,
+    /// Number of cache texels written by the last upload.
+    pub uploaded_texels: u64,
+    /// `true` if the last upload had to grow (recreate) the cache texture.
+    pub resized: bool,
+    /// Number of cache texture resizes since the brush was built.
+    pub resizes: u32,
+}
+
+/// Laid out glyph with its pixel rectangle, see [`TextBrush::glyph_rects()`].
+#[derive(Debug, Clone)]
+pub struct GlyphRect {
+    /// The glyph, with the section text index and byte offset of its character.
+    pub glyph: SectionGlyph,
+    /// Layout bounds of the glyph in pixels.
+    pub rect: Rect,
+}
+
+/// Visual line of laid out glyphs.
+struct Line {
+    baseline: f32,
+    ascent: f32,
+    /// Descent including the line gap.
+    descent: f32,
+    min_x: f32,
+    max_x: f32,
+}
+
+/// Post-layout adjustments of section glyphs, see [`TextBrush::queue_glyphs`].
+#[derive(Clone, Copy, PartialEq)]
+struct GlyphAdjustments {
+    reveal: f32,
+    hide_zero_width_spaces: bool,
+    run_align: VerticalRunAlign,
+    hinting: Hinting,
+    jitter: Option<Jitter>,
+    subpixel: bool,
+    edge_fades: [Option<(FadeEdge, f32)>; 4],
+    tabular_figures: bool,
+    letter_spacing: f32,
+    tab_width: f32,
+    line_height: f32,
+    layout_mode: LayoutMode,
+    shadow: Option<((f32, f32), Color)>,
+    gradient: Option<(Color, GradientDirection)>,
+    transform: Option<Transform2D>,
+    local_origin: Option<(f32, f32)>,
+    /// Clipping rectangle replacing the section bounds.
+    clip: Option<Rect>,
+    color_glyphs: bool,
+}
+
+impl GlyphAdjustments {
+    const NONE: Self = Self {
+        reveal: 1.0,
+        hide_zero_width_spaces: false,
+        run_align: VerticalRunAlign::Baseline,
+        hinting: Hinting::None,
+        jitter: None,
+        subpixel: false,
+        edge_fades: [None; 4],
+        tabular_figures: false,
+        letter_spacing: 0.0,
+        tab_width: 0.0,
+        line_height: 1.0,
+        layout_mode: LayoutMode::Horizontal,
+        shadow: None,
+        gradient: None,
+        transform: None,
+        local_origin: None,
+        clip: None,
+        color_glyphs: false,
+    };
+}
+
+/// Cache texture growth policy, see [`BrushBuilder::cache_growth()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrowthPolicy {
+    /// Doubles both cache texture dimensions.
+    #[default]
+    Double,
+    /// Grows both cache texture dimensions by the provided number of texels, but at
+    /// least by an eighth of the current dimension. Each resize rasterizes all glyphs
+    /// again, so smaller steps are raised to that minimum.
+    Step(u32),
+    /// Grows both cache texture dimensions by an eighth per resize until all glyphs
+    /// fit. It approximates growing to the needed size, which **glyph_brush** doesn't
+    /// report: the texture ends up at most about 12.5% bigger than needed, at the cost
+    /// of more resizes than [`GrowthPolicy::Double`].
+    Gradual,
+}
+
+impl GrowthPolicy {
+    /// Returns the next cache texture dimensions for the `current` ones, where
+    /// `suggested` are the doubled dimensions suggested by **glyph_brush**.
+    fn grow(self, current: (u32, u32), suggested: (u32, u32)) -> (u32, u32) {
+        let grow = |v: u32, by: u32| v.saturating_add(by.max(v / 8).max(1));
+        match self {
+            GrowthPolicy::Double => suggested,
+            GrowthPolicy::Step(step) => (grow(current.0, step), grow(current.1, step)),
+            GrowthPolicy::Gradual => (grow(current.0, 0), grow(current.1, 0)),
+        }
+    }
+}
+
+/// Hinting preference, see [`BrushBuilder::hinting()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Hinting {
+    /// Glyphs are placed at their exact, sub-pixel positions.
+    #[default]
+    None,
+    /// Glyph baselines are snapped to whole pixels.
+    Vertical,
+    /// Glyph origins are snapped to whole pixels on both axes.
+    Full,
+}
+
+/// Section ready for layout, owning its text if it had to be modified.
+enum PreparedSection<'a> {
+    Section(Cow<'a, Section<'a>>),
+    Owned(OwnedSection),
+}
+
+impl PreparedSection<'_> {
+    /// Returns the section for the inner glyph brush.
+    fn section(&self) -> Section<'_, GlyphExtra> {
+        match self {
+            PreparedSection::Section(section) => glyph_section(section),
+            PreparedSection::Owned(owned) => glyph_section(&owned.to_borrowed()),
+        }
+    }
 }
 
-impl<F, H> TextBrush<F, H>
-where
-    F: Font + Sync,
-    H: std::hash::BuildHasher,
-{
-    /// Queues section for drawing, processes all queued text and updates the
-    /// inner vertex buffer, unless the text vertices remain unmodified when
-    /// compared to the last frame.
-    ///
-    /// If utilizing *depth*, the `sections` list should have `Section`s ordered from
-    /// furthest to closest. They will be drawn in the order they are given.
-    ///
-    /// - This method should be called every frame.
-    ///
-    /// If not called when required, the draw functions will continue drawing data from the
-    /// inner vertex buffer meaning they will redraw old vertices.
-    ///
-    /// To learn about GPU texture caching, see
-    /// [`caching behaviour`](https://docs.rs/glyph_brush/latest/glyph_brush/struct.GlyphBrush.html#caching-behaviour)
-    #[inline]
-    pub fn queue<'a, S>(
-        &mut self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        sections: Vec<S>,
-    ) -> Result<(), BrushError>
-    where
-        S: Into<std::borrow::Cow<'a, Section<'a>>>,
-    {
-        // Queue sections:
-        for s in sections {
-            self.inner.queue(s);
-        }
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
 
-        // Process sections:
-        loop {
-            // Contains BrushAction enum which marks for
-            // drawing or redrawing (using old data).
-            let brush_action = self.inner.process_queued(
-                |rect, data| self.pipeline.update_texture(rect, data, queue),
-                Vertex::to_vertex,
-            );
+/// Size of the staging belt chunks in bytes, bigger uploads get their own chunks.
+const STAGING_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
 
-            match brush_action {
-                Ok(action) => {
-                    break match action {
-                        BrushAction::Draw(vertices) => {
-                            self.pipeline.update_vertex_buffer(vertices, device, queue)
-                        }
-                        BrushAction::ReDraw => (),
-                    }
-                }
+/// Horizontal supersampling of glyphs drawn with subpixel rendering.
+const SUBPIXELS: f32 = 3.0;
 
-                Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
-                    if log::log_enabled!(log::Level::Warn) {
-                        log::warn!(
-                            "Resizing cache texture! This should be avoided \
-                            by building TextBrush with BrushBuilder::initial_cache_size() \
-                            and providing bigger cache texture dimensions."
-                        );
-                    }
-                    // Texture resizing:
-                    let max_image_dimension = device.limits().max_texture_dimension_2d;
-                    let (width, height) = if suggested.0 > max_image_dimension
-                        || suggested.1 > max_image_dimension
-                    {
-                        if self.inner.texture_dimensions().0 < max_image_dimension
-                            || self.inner.texture_dimensions().1 < max_image_dimension
-                        {
-                            (max_image_dimension, max_image_dimension)
-                        } else {
-                            return Err(BrushError::TooBigCacheTexture(
-                                max_image_dimension,
-                            ));
-                        }
-                    } else {
-                        suggested
-                    };
-                    self.pipeline.resize_texture(device, (width, height));
-                    self.inner.resize_texture(width, height);
-                }
+/// Overwrites the elements of `old` with the `new` ones, returning the range of
+/// elements that changed, which is empty if none did.
+fn overwrite<T: PartialEq>(
+    old: &mut Vec<T>,
+    new: impl Iterator<Item = T>,
+) -> Range<usize> {
+    let (mut changed, mut len) = (None, 0);
+    for (i, item) in new.enumerate() {
+        let unchanged = match old.get_mut(i) {
+            Some(old) if *old == item => true,
+            Some(old) => {
+                *old = item;
+                false
             }
+            None => {
+                old.push(item);
+                false
+            }
+        };
+        if !unchanged {
+            changed = Some(changed.map_or(i, |(first, _)| first)).zip(Some(i + 1));
         }
-        Ok(())
+        len = i + 1;
     }
+    old.truncate(len);
+    changed.map_or(0..0, |(first, last)| first..last)
+}
 
-    /// Returns a bounding box for the section glyphs calculated using each
-    /// glyph's vertical & horizontal metrics. For more info, read about
-    /// [`GlyphCruncher::glyph_bounds`].
-    #[inline]
-    pub fn glyph_bounds<'a, S>(&mut self, section: S) -> Option<Rect>
-    where
-        S: Into<std::borrow::Cow<'a, Section<'a>>>,
+/// See [`rebuild`].
+type RebuildFn<F, H> = fn(&mut glyph_brush::GlyphBrush<Instance, GlyphExtra, F, H>);
+
+/// Rebuilds `inner` with its current settings and cache texture dimensions, dropping
+/// its queued sections and cached glyphs.
+fn rebuild<F, H>(inner: &mut glyph_brush::GlyphBrush<Instance, GlyphExtra, F, H>)
+where
+    F: Font + Clone,
+    H: std::hash::BuildHasher + Clone,
+{
+    *inner = inner.to_builder().build();
+}
+
+/// Color glyph texture size of [`BrushBuilder::prefer_bitmap_strikes`] without color
+/// emoji.
+const STRIKE_CACHE_SIZE: u32 = 512;
+
+/// Returns `text` with control characters substituted or removed (see
+/// [`BrushBuilder::sanitize_control_chars()`]), `None` if there are none. Tabs are
+/// kept with `keep_tabs`, for sections with tab stops.
+fn sanitize_text(text: &str, keep_tabs: bool) -> Option<String> {
+    let kept = |c: char| c == '\n' || (keep_tabs && c == '\t');
+    if !text
+        .chars()
+        .any(|c| (c.is_control() && !kept(c)) || c == '\u{FEFF}')
     {
-        self.inner.glyph_bounds(section)
+        return None;
     }
 
-    /// Returns an iterator over the `PositionedGlyph`s of the given section.
-    #[inline]
-    pub fn glyphs_iter<'a, 'b, S>(&'b mut self, section: S) -> SectionGlyphIter<'b>
-    where
-        S: Into<std::borrow::Cow<'a, Section<'a>>>,
-    {
-        self.inner.glyphs(section)
+    let mut sanitized = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\r' | '\n' => sanitized.push('\n'),
+            '\t' if keep_tabs => sanitized.push('\t'),
+            '\t' => sanitized.push(' '),
+            '\u{FEFF}' => (),
+            c if c.is_control() => (),
+            c => sanitized.push(c),
+        }
     }
+    Some(sanitized)
+}
 
-    /// Returns the available fonts.
-    ///
-    /// The `FontId` corresponds to the index of the font data.
-    pub fn fonts(&self) -> &[F] {
-        self.inner.fonts()
+/// Returns the render `matrix` applied to the y-down layout space of text, negating
+/// the layout y coordinates first for [`YAxis::Up`].
+fn layout_matrix(mut matrix: Matrix, y_axis: YAxis) -> Matrix {
+    if y_axis == YAxis::Up {
+        matrix[1] = matrix[1].map(|v| -v);
     }
+    matrix
+}
 
-    /// Draws all sections queued with [`queue`](#method.queue) function.
-    #[inline]
-    pub fn draw<'pass>(&'pass mut self, rpass: &mut wgpu::RenderPass<'pass>) {
-        self.pipeline.draw(rpass)
+/// Returns the matrix product `a * b` of column-major matrices, applying `b` first.
+fn mul_matrix(a: Matrix, b: Matrix) -> Matrix {
+    let mut product = [[0.0; 4]; 4];
+    for (column, b_column) in product.iter_mut().zip(b) {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+        }
     }
+    product
+}
 
-    /// Resizes the view matrix. Updates the default orthographic view matrix with
-    /// provided dimensions and uses it for rendering.
-    ///
-    /// Run this function whenever the surface config is resized.
-    /// **Surface** dimensions are most commonly *width* and *height*.
-    ///
-    /// **Matrix**:
-    /// ```rust
-    /// pub fn ortho(width: f32, height: f32) -> [[f32; 4]; 4] {
-    ///     [
-    ///         [2.0 / width, 0.0,          0.0, 0.0],
-    ///         [0.0,        -2.0 / height, 0.0, 0.0],
-    ///         [0.0,         0.0,          1.0, 0.0],
-    ///         [-1.0,        1.0,          0.0, 1.0]
-    ///     ]
-    /// }
-    /// ```
-    #[inline]
-    pub fn resize_view(&mut self, width: f32, height: f32, queue: &wgpu::Queue) {
-        self.update_matrix(crate::ortho(width, height), queue);
+/// Spreads glyphs of each line of the `section` apart by `spacing` pixels, keeping
+/// the lines aligned by the section horizontal alignment.
+fn letter_spacing_glyphs(
+    section: &Section<GlyphExtra>,
+    glyphs: &mut [SectionGlyph],
+    spacing: f32,
+) {
+    let h_align = match section.layout {
+        Layout::SingleLine { h_align, .. } | Layout::Wrap { h_align, .. } => h_align,
+    };
+    let mut start = 0;
+    while start < glyphs.len() {
+        let y = glyphs[start].glyph.position.y;
+        let len = glyphs[start..]
+            .iter()
+            .take_while(|glyph| glyph.glyph.position.y == y)
+            .count();
+        let total = spacing * (len - 1) as f32;
+        let offset = match h_align {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => -total / 2.0,
+            HorizontalAlign::Right => -total,
+        };
+        for (i, glyph) in glyphs[start..start + len].iter_mut().enumerate() {
+            glyph.glyph.position.x += offset + spacing * i as f32;
+        }
+        start += len;
     }
+}
 
-    /// Resizes the view. Updates text rendering matrix with the provided one.
-    ///
-    /// Use [`Self::resize_view()`] to update and replace the current render matrix
-    /// with a default orthographic matrix.
-    ///
-    /// Feel free to use [`ortho()`] to create more complex matrices by yourself.
-    #[inline]
-    pub fn update_matrix<M>(&mut self, matrix: M, queue: &wgpu::Queue)
-    where
-        M: Into<Matrix>,
-    {
-        self.pipeline.update_matrix(matrix.into(), queue);
+/// Moves the lines of `glyphs` away from the first line, multiplying the vertical
+/// advance between lines by `multiplier`.
+fn line_height_glyphs(glyphs: &mut [SectionGlyph], multiplier: f32) {
+    if let Some(first) = glyphs.first().map(|glyph| glyph.glyph.position.y) {
+        for glyph in glyphs {
+            let y = &mut glyph.glyph.position.y;
+            *y = first + (*y - first) * multiplier;
+        }
     }
 }
 
+/// Linearly interpolates between colors `a` and `b`.
+#[inline]
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
 /// Builder for [`TextBrush`].
+///
+/// Besides its own options, it forwards the tuning options of
+/// [`glyph_brush::GlyphBrushBuilder`] with the same defaults, like
+/// `cache_glyph_positioning()`, `cache_redraws()`, `draw_cache_position_tolerance()`,
+/// `draw_cache_scale_tolerance()`, `initial_cache_size()` and `multithread()`, which
+/// trade memory for fewer layouts and rasterizations.
 #[non_exhaustive]
 pub struct BrushBuilder<F, H = DefaultSectionHasher> {
     inner: glyph_brush::GlyphBrushBuilder<F, H>,
     depth_stencil: Option<wgpu::DepthStencilState>,
     multisample: wgpu::MultisampleState,
     multiview: Option<NonZeroU32>,
+    blend_mode: BlendMode,
     matrix: Option<Matrix>,
+    scale_quantization: Option<f32>,
+    sanitize_control_chars: bool,
+    fallback_fonts: Vec<FontId>,
+    hinting: Hinting,
+    cache_growth: GrowthPolicy,
+    subpixel: bool,
+    scale_factor: f32,
+    depth_layers: u32,
+    y_axis: YAxis,
+    vertex_capacity: usize,
+    vertex_buffer_usage: wgpu::BufferUsages,
+    sampler_filters: SamplerFilters,
+    fragment_shader: Option<wgpu::ShaderModuleDescriptor<'static>>,
+    srgb: bool,
+    max_cache_size: Option<(u32, u32)>,
+    rebuild: Option<RebuildFn<F, H>>,
+    premultiplied: bool,
+    gamma: f32,
+    color_emoji: Option<u32>,
+    bitmap_strikes: bool,
 }
 
 impl BrushBuilder<()> {
@@ -191,7 +2880,9 @@ impl BrushBuilder<()> {
     }
 
     /// Creates a [`BrushBuilder`] with font byte data.
-    pub fn using_font_bytes(data: &[u8]) -> Result<BrushBuilder<FontRef>, InvalidFont> {
+    pub fn using_font_bytes(
+        data: &[u8],
+    ) -> Result<BrushBuilder<FontRef<'_>>, InvalidFont> {
         let font = FontRef::try_from_slice(data)?;
         Ok(BrushBuilder::using_fonts(vec![font]))
     }
@@ -199,7 +2890,7 @@ impl BrushBuilder<()> {
     /// Creates a [`BrushBuilder`] with multiple fonts byte data.
     pub fn using_font_bytes_vec(
         data: &[u8],
-    ) -> Result<BrushBuilder<FontRef>, InvalidFont> {
+    ) -> Result<BrushBuilder<FontRef<'_>>, InvalidFont> {
         let font = FontRef::try_from_slice(data)?;
         Ok(BrushBuilder::using_fonts(vec![font]))
     }
@@ -211,7 +2902,31 @@ impl BrushBuilder<()> {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
+            blend_mode: BlendMode::default(),
             matrix: None,
+            scale_quantization: None,
+            sanitize_control_chars: false,
+            fallback_fonts: Vec::new(),
+            hinting: Hinting::None,
+            cache_growth: GrowthPolicy::Double,
+            subpixel: false,
+            scale_factor: 1.0,
+            depth_layers: 16,
+            y_axis: YAxis::Down,
+            vertex_capacity: 0,
+            vertex_buffer_usage: wgpu::BufferUsages::empty(),
+            sampler_filters: SamplerFilters {
+                mag: wgpu::FilterMode::Linear,
+                min: wgpu::FilterMode::Linear,
+            },
+            fragment_shader: None,
+            srgb: false,
+            max_cache_size: None,
+            rebuild: None,
+            premultiplied: false,
+            gamma: 1.0,
+            color_emoji: None,
+            bitmap_strikes: false,
         }
     }
 }
@@ -224,6 +2939,15 @@ where
     // Default `BrushBuilder` functions:
     glyph_brush::delegate_glyph_brush_builder_fns!(inner);
 
+    /// When multiple CPU cores are available, spreads the glyph rasterization work of
+    /// the inner draw cache across all of them.
+    ///
+    /// Defaults to `true`.
+    pub fn multithread(mut self, multithread: bool) -> Self {
+        self.inner = self.inner.multithread(multithread);
+        self
+    }
+
     /// Uses the provided `matrix` when rendering.
     ///
     /// To update the render matrix use [`TextBrush::update_matrix()`].
@@ -237,6 +2961,12 @@ where
 
     /// Provide the `wgpu::MultisampleState` used by the inner pipeline.
     ///
+    /// Its `count` must match the sample count of the render pass color attachment,
+    /// e.g. `4` for drawing into a 4x MSAA target. When drawing with a *depth_stencil*
+    /// ([`Self::with_depth_stencil()`]), the depth attachment must have the same sample
+    /// count too. [`TextBrush::render_to_texture()`] draws without resolving, so its
+    /// target view has to match as well.
+    ///
     /// Defaults to value returned by [`wgpu::MultisampleState::default()`].
     pub fn with_multisample(mut self, multisample: wgpu::MultisampleState) -> Self {
         self.multisample = multisample;
@@ -251,6 +2981,252 @@ where
         self
     }
 
+    /// Provide the [`BlendMode`] used for blending text with the render target.
+    ///
+    /// Defaults to [`BlendMode::Alpha`]. Can be changed later with
+    /// [`TextBrush::set_blend_mode()`].
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Provide a custom [`wgpu::BlendState`] used for blending text with the render
+    /// target, shorthand for [`Self::with_blend_mode()`] with [`BlendMode::Custom`].
+    ///
+    /// The fragment shader outputs straight (not premultiplied) alpha, unless enabled
+    /// with [`Self::with_premultiplied_alpha()`].
+    pub fn with_blend_state(self, blend_state: wgpu::BlendState) -> Self {
+        self.with_blend_mode(BlendMode::Custom(blend_state))
+    }
+
+    /// Provide a shader module with a custom `fs_main` fragment entry point, replacing
+    /// the built-in one for effects like dithering or animated colors.
+    ///
+    /// The module is used together with the built-in vertex shader, so it must
+    /// declare the same interface:
+    /// - the input of `vs_main`, a struct with `@location(0) tex_pos: vec2<f32>`
+    ///   (cache texture position), `@location(1) color: vec4<f32>` (text color),
+    ///   `@location(2) @interpolate(flat) tex_rect: vec4<f32>` (glyph region in the
+    ///   cache texture), `@location(3) @interpolate(flat) tex_per_px: vec2<f32>` and
+    ///   `@location(4) mask_pos: vec2<f32>`,
+    /// - `@group(0) @binding(1)` cache `texture_2d<f32>` holding glyph coverage in its
+    ///   only (R8) channel and its `@group(0) @binding(2)` sampler,
+    /// - the output `@location(0) vec4<f32>` with straight (not premultiplied) alpha.
+    ///
+    /// Bind groups of effects (1) and masks (2) can be declared as in the built-in
+    /// shader but don't have to be. Subpixel drawing keeps using the built-in
+    /// fragment shader.
+    pub fn with_fragment_shader(
+        mut self,
+        shader: wgpu::ShaderModuleDescriptor<'static>,
+    ) -> Self {
+        self.fragment_shader = Some(shader);
+        self
+    }
+
+    /// Outputs colors with premultiplied alpha (color multiplied by the coverage and
+    /// alpha) and blends them with [`wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING`]
+    /// (or its additive counterpart), avoiding dark fringes when compositing text
+    /// into translucent off-screen buffers which are premultiplied themselves.
+    ///
+    /// [`BlendMode::Custom`] blend states are used unchanged and have to expect
+    /// premultiplied colors. Subpixel drawing ([`Self::subpixel_rgb()`]) and custom
+    /// fragment shaders aren't affected. Defaults to `false`.
+    pub fn with_premultiplied_alpha(mut self, premultiplied: bool) -> Self {
+        self.premultiplied = premultiplied;
+        self
+    }
+
+    /// Raises the glyph coverage to the power of `gamma` before blending, like the
+    /// gamma correction of FreeType. Values below `1.0` darken and thicken thin fonts
+    /// which render too light, values above `1.0` lighten bold ones. Non positive
+    /// values are ignored.
+    ///
+    /// Applies to all draw functions and subpixel drawing, but not to solid quads,
+    /// color glyphs and custom fragment shaders. Defaults to `1.0`.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        if gamma > 0.0 {
+            self.gamma = gamma;
+        }
+        self
+    }
+
+    /// Draws color bitmap glyphs, like the emoji of `CBDT` and `sbix` fonts, in full
+    /// color. Their images are cached in a separate RGBA texture of `cache_size` x
+    /// `cache_size` texels, as the cache texture only holds coverage, and cleared once
+    /// it gets full.
+    ///
+    /// Images are cached at the largest size the font provides and scaled down when
+    /// drawn, tinted only by the alpha of their text color. PNG images, used by most
+    /// `CBDT` emoji fonts like Noto Color Emoji, need the `emoji` feature. Vector
+    /// color glyphs (`COLR`) aren't supported, their outlines are drawn with coverage
+    /// as usual.
+    ///
+    /// Defaults to no color glyphs.
+    pub fn with_color_emoji(mut self, cache_size: u32) -> Self {
+        self.color_emoji = Some(cache_size.max(1));
+        self
+    }
+
+    /// Draws glyphs from the embedded bitmap strike of their font size, if the font has
+    /// one, instead of rasterizing their outlines. Fonts with strikes hand-tuned for
+    /// small sizes are drawn pixel-perfect that way, on whole pixel positions.
+    ///
+    /// Only strikes of exactly the glyph's pixel size are used, other sizes are
+    /// rasterized as usual. Grayscale and monochrome strikes are drawn in the text
+    /// color, color strikes as with [`Self::with_color_emoji()`]. Strikes are cached
+    /// in the color glyph texture, 512 x 512 texels unless its size is set with
+    /// [`Self::with_color_emoji()`].
+    ///
+    /// Defaults to `false`.
+    pub fn prefer_bitmap_strikes(mut self, prefer: bool) -> Self {
+        self.bitmap_strikes = prefer;
+        self
+    }
+
+    /// Quantizes the scale glyphs are cached at to the nearest multiple of `step`
+    /// pixels, while text is still laid out at its exact scale.
+    ///
+    /// During smooth zoom animations every slightly different scale would rasterize a new
+    /// glyph variant into the cache texture. With quantization, nearby scales share the
+    /// same cached glyphs, stretched to their exact size, so text keeps zooming smoothly
+    /// at the cost of slightly blurrier glyphs for big steps. Overrides
+    /// [`Self::draw_cache_scale_tolerance()`], which it sets to `step`.
+    ///
+    /// Cached glyphs are also told apart by their subpixel position, which changes with
+    /// the scale too, so raise [`Self::draw_cache_position_tolerance()`] as well to
+    /// reuse glyphs regardless of it (any tolerance of `2.0` or more).
+    ///
+    /// A `step` of `0.0` or less disables quantization, which is the default.
+    pub fn scale_quantization(mut self, step: f32) -> Self {
+        self.scale_quantization = (step > 0.0).then_some(step);
+        self
+    }
+
+    /// Sanitizes control characters of all queued and measured text, which would
+    /// otherwise be drawn as missing glyphs (tofu) or have odd advances. Useful for
+    /// text from logs or user input.
+    ///
+    /// Affected characters:
+    /// - `\r\n` and a lone `\r` become `\n`,
+    /// - `\t` becomes a space, unless the section has
+    ///   [tab stops](crate::SectionExt::tab_width),
+    /// - U+FEFF (byte order mark) and all other control characters (U+0000–U+001F and
+    ///   U+007F–U+009F, except `\n`) are removed,
+    /// - U+200B (zero width space) is kept, so wrapping can still break lines at it, but
+    ///   isn't drawn.
+    ///
+    /// Defaults to `false`.
+    pub fn sanitize_control_chars(mut self, sanitize: bool) -> Self {
+        self.sanitize_control_chars = sanitize;
+        self
+    }
+
+    /// Sets the fallback fonts, by their [`FontId`] (index in the fonts provided to
+    /// [`BrushBuilder::using_fonts()`]), searched in order for characters the font of
+    /// their text doesn't have, like emoji or CJK characters in a Latin font.
+    ///
+    /// Texts with missing characters are split into runs drawn with the first font
+    /// having them, before layout. Characters no font has are drawn with the text
+    /// font. Checking every character has a small cost, so it's only done when
+    /// fallback fonts are set.
+    ///
+    /// Defaults to no fallback fonts.
+    pub fn with_fallback_fonts(mut self, fonts: Vec<FontId>) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
+    /// Sets how the cache texture grows when queued glyphs don't fit into it.
+    ///
+    /// Doubling the dimensions needs the fewest resizes, but can waste a lot of GPU
+    /// memory when only slightly more space is needed. Memory constrained devices can
+    /// trade a few extra resizes (each one rasterizes all glyphs again) for a lower
+    /// peak cache texture size with [`GrowthPolicy::Step`] or [`GrowthPolicy::Gradual`].
+    ///
+    /// Defaults to [`GrowthPolicy::Double`].
+    pub fn cache_growth(mut self, policy: GrowthPolicy) -> Self {
+        self.cache_growth = policy;
+        self
+    }
+
+    /// Limits the dimensions the cache texture may grow to. Growth is clamped to `max`
+    /// and once the cache texture can't grow any more, queuing fails with
+    /// [`BrushError::CacheSizeExceeded`] instead of creating an oversized texture.
+    /// The initial dimensions are set with `initial_cache_size()` and shouldn't
+    /// exceed `max`.
+    ///
+    /// The failed sections are dropped along with all cached glyphs, so less text can
+    /// be queued right after the error. That's why the fonts need to be [`Clone`],
+    /// like [`FontArc`] and [`FontRef`].
+    ///
+    /// Useful on mobile GPUs with a small `max_texture_dimension_2d` or to cap the GPU
+    /// memory used by text. Defaults to the `max_texture_dimension_2d` limit of the
+    /// device, exceeding which fails with [`BrushError::TooBigCacheTexture`].
+    pub fn with_max_cache_size(mut self, max: (u32, u32)) -> Self
+    where
+        F: Clone,
+        H: Clone,
+    {
+        self.max_cache_size = Some(max);
+        self.rebuild = Some(rebuild::<F, H>);
+        self
+    }
+
+    /// Provide the DPI scale factor of the window, the ratio of physical to logical
+    /// pixels. See [`TextBrush::to_physical()`].
+    ///
+    /// Defaults to `1.0`. Can be changed later with [`TextBrush::set_scale_factor()`].
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Enables subpixel anti-aliasing for RGB stripe displays, tripling the horizontal
+    /// resolution of text drawn with [`TextBrush::draw()`].
+    ///
+    /// Glyphs are cached with three times the horizontal resolution (so they take up
+    /// three times more cache texture space) and the red, green and blue channels get
+    /// their own coverage. As **wgpu** has no dual-source blending, [`TextBrush::draw()`]
+    /// draws the text twice, first masking the render target with the per channel
+    /// coverage and then adding the text color, so the [`BlendMode`] is ignored by it.
+    /// Other draw functions keep using grayscale coverage.
+    ///
+    /// Only use it when drawing onto opaque content with a known horizontal RGB
+    /// subpixel layout and without scaling or rotating the text.
+    pub fn subpixel_rgb(mut self) -> Self {
+        self.subpixel = true;
+        self
+    }
+
+    /// Sets whether text colors are treated as sRGB encoded and converted to linear
+    /// ones before being written to the render target.
+    ///
+    /// GPUs encode the output of `*Srgb` render formats (like
+    /// [`wgpu::TextureFormat::Bgra8UnormSrgb`]) themselves and blend in linear space,
+    /// so usual sRGB colors (e.g. picked from a color picker) have to be linearized
+    /// first, otherwise they look washed out. Enable it for `*Srgb` render formats
+    /// unless you provide linear colors yourself.
+    ///
+    /// Defaults to `false`, colors are written unchanged for any render format.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Sets the [`Hinting`] preference, which can make small text less fuzzy.
+    ///
+    /// The **ab_glyph** rasterizer doesn't support hinting, **glyph outlines are never
+    /// adjusted**. Instead, [`Hinting::Vertical`] and [`Hinting::Full`] snap glyph
+    /// origins to the pixel grid, so glyphs don't get blurred by sub-pixel positioning.
+    /// Snapping is done after layout, so glyph advances and measurement are unaffected.
+    ///
+    /// Defaults to [`Hinting::None`].
+    pub fn hinting(mut self, hinting: Hinting) -> Self {
+        self.hinting = hinting;
+        self
+    }
+
     /// Provide the *depth_stencil* if you are planning to utilize depth testing.
     ///
     /// For each section, depth can be set by modifying the z coordinate
@@ -258,6 +3234,17 @@ where
     ///
     /// `z` coordinate should be in range
     ///  [0.0, 1.0] not including 1.0.
+    ///
+    /// The glyph *z* is passed through the view matrix as the fragment depth, so text
+    /// is tested against the depth attachment of the render pass given to the draw
+    /// functions, using the `depth_compare` of the provided state. Text only writes
+    /// depth if `depth_write_enabled` is set. Glyph quads write depth over their whole
+    /// area, transparent edges included, so with depth writes enabled draw text after
+    /// the geometry behind it, and overlapping sections from furthest to closest.
+    ///
+    /// The state can enable stencil testing too, e.g. a `stencil` with a front and back
+    /// `compare` of [`wgpu::CompareFunction::Equal`] draws text only where the stencil
+    /// buffer equals the reference of [`TextBrush::draw_with_stencil()`].
     pub fn with_depth_stencil(
         mut self,
         depth_stencil: Option<wgpu::DepthStencilState>,
@@ -266,11 +3253,110 @@ where
         self
     }
 
+    /// Sets the number of depth layers sections can be placed into with
+    /// [`SectionExt::layer()`](crate::SectionExt::layer), see [`TextBrush::layer_z()`].
+    ///
+    /// Defaults to `16`. Can be changed later with [`TextBrush::set_depth_layers()`].
+    pub fn with_depth_layers(mut self, count: u32) -> Self {
+        self.depth_layers = count.max(1);
+        self
+    }
+
+    /// Sets the direction of the y axis of section positions, the default orthographic
+    /// matrix (see [`YAxis::ortho()`]) and the rectangles and points of the measuring
+    /// and hit testing functions.
+    ///
+    /// With [`YAxis::Up`], section positions are still the top left corner of their
+    /// text, which keeps flowing downwards, the layout of **glyph_brush** is flipped
+    /// into the y-up coordinates by the render matrix. Matrices provided with
+    /// [`Self::with_matrix()`] or [`TextBrush::update_matrix()`] should map y-up
+    /// coordinates as well. Glyphs of [`TextBrush::glyphs_iter()`] are in the y-down
+    /// layout space, with negated y coordinates.
+    ///
+    /// Defaults to [`YAxis::Down`].
+    pub fn with_y_axis(mut self, y_axis: YAxis) -> Self {
+        self.y_axis = y_axis;
+        self
+    }
+
+    /// Sets the filter used for both magnifying and minifying glyphs sampled from the
+    /// cache texture. Use [`wgpu::FilterMode::Nearest`] for crisp bitmap or pixel fonts
+    /// drawn at integer scales. See [`Self::with_sampler_filters()`].
+    ///
+    /// Defaults to [`wgpu::FilterMode::Linear`]. Can be changed later with
+    /// [`TextBrush::set_sampler_filters()`].
+    pub fn with_sampler_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.with_sampler_filters(filter, filter)
+    }
+
+    /// Sets the cache texture sampler filters separately for magnification (`mag`)
+    /// and minification (`min`), e.g. nearest magnification but linear minification.
+    ///
+    /// Defaults to [`wgpu::FilterMode::Linear`] for both.
+    pub fn with_sampler_filters(
+        mut self,
+        mag: wgpu::FilterMode,
+        min: wgpu::FilterMode,
+    ) -> Self {
+        self.sampler_filters = SamplerFilters { mag, min };
+        self
+    }
+
+    /// Sizes the inner vertex buffer to hold `capacity` vertices (one per glyph or line
+    /// background) from the start, so apps drawing a steady amount of text don't
+    /// reallocate it during the first frames.
+    ///
+    /// The buffer grows to 1.5 times the needed size when it's too small and never
+    /// shrinks. See [`TextBrush::vertex_capacity()`].
+    ///
+    /// Defaults to `0`.
+    pub fn with_vertex_capacity(mut self, capacity: usize) -> Self {
+        self.vertex_capacity = capacity;
+        self
+    }
+
+    /// Adds `usage` flags (like [`wgpu::BufferUsages::STORAGE`] or
+    /// [`wgpu::BufferUsages::COPY_SRC`]) to the inner vertex buffer, which is always
+    /// created with `VERTEX | COPY_DST`, so the glyph instances can be processed on
+    /// the GPU, e.g. animated by a compute shader. See [`TextBrush::vertex_buffer()`].
+    ///
+    /// Defaults to no additional usages.
+    pub fn with_vertex_buffer_usage(mut self, usage: wgpu::BufferUsages) -> Self {
+        self.vertex_buffer_usage = usage;
+        self
+    }
+
+    /// Selects the instance of variable fonts by setting the variation axis `tag` (like
+    /// `b"wght"` for weight or `b"wdth"` for width) to `value`, before any glyphs are
+    /// cached. Call multiple times to set multiple axes.
+    ///
+    /// The axis is set on every font that has it. Returns
+    /// [`BrushError::UnknownVariationAxis`] if none of the fonts has the axis. Only
+    /// available for font types which can vary, like [`FontRef`] and
+    /// [`FontVec`](glyph_brush::ab_glyph::FontVec).
+    pub fn with_variation(mut self, tag: &[u8; 4], value: f32) -> Result<Self, BrushError>
+    where
+        F: VariableFont,
+    {
+        let mut found = false;
+        for font in &mut self.inner.font_data {
+            found |= font.set_variation(tag, value);
+        }
+        match found {
+            true => Ok(self),
+            false => Err(BrushError::UnknownVariationAxis(*tag)),
+        }
+    }
+
     /// Builds a [`TextBrush`] while consuming [`BrushBuilder`], for later drawing text
     /// onto a texture of the specified `render_width`, `render_height` and [`wgpu::TextureFormat`].
     ///
     /// If you are drawing a basic UI, you'd most likely want to be using
     /// [`wgpu::SurfaceConfiguration`]'s dimensions and texture format.
+    ///
+    /// If the `device` has [`wgpu::Features::PUSH_CONSTANTS`] enabled, the render
+    /// matrix is pushed with every draw instead of being written into a uniform
+    /// buffer on every matrix change.
     pub fn build(
         self,
         device: &wgpu::Device,
@@ -278,22 +3364,140 @@ where
         render_height: u32,
         render_format: wgpu::TextureFormat,
     ) -> TextBrush<F, H> {
-        let inner = self.inner.build();
+        let inner = match self.scale_quantization {
+            Some(step) => self.inner.draw_cache_scale_tolerance(step).build(),
+            None => self.inner.build(),
+        };
+        let cache_size = inner.texture_dimensions();
+        let color_cache_size = match self.bitmap_strikes {
+            true => Some(self.color_emoji.unwrap_or(STRIKE_CACHE_SIZE)),
+            false => self.color_emoji,
+        };
 
-        let matrix = self
-            .matrix
-            .unwrap_or_else(|| crate::ortho(render_width as f32, render_height as f32));
+        let matrix = self.matrix.unwrap_or_else(|| {
+            self.y_axis.ortho(render_width as f32, render_height as f32)
+        });
 
+        let config = PipelineConfig {
+            render_format,
+            depth_stencil: self.depth_stencil,
+            multisample: self.multisample,
+            multiview: self.multiview,
+            blend_mode: self.blend_mode,
+            subpixel: self.subpixel,
+            srgb: self.srgb,
+            premultiplied: self.premultiplied,
+            gamma: self.gamma,
+            push_constants: device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+                && device.limits().max_push_constant_size
+                    >= std::mem::size_of::<Matrix>() as u32,
+            vertex_usage: self.vertex_buffer_usage,
+            color_cache_size: color_cache_size.unwrap_or(1),
+        };
         let pipeline = Pipeline::new(
             device,
-            render_format,
-            self.depth_stencil,
-            self.multisample,
-            self.multiview,
-            inner.texture_dimensions(),
-            matrix,
+            config,
+            cache_size,
+            layout_matrix(matrix, self.y_axis),
+            self.sampler_filters,
+            self.vertex_capacity,
+            self.fragment_shader,
         );
 
-        TextBrush { inner, pipeline }
+        TextBrush {
+            inner,
+            pipeline,
+            sanitize_control_chars: self.sanitize_control_chars,
+            fallback_fonts: self.fallback_fonts,
+            hinting: self.hinting,
+            cache_growth: self.cache_growth,
+            subpixel: self.subpixel,
+            scale_factor: self.scale_factor,
+            depth_layers: self.depth_layers,
+            y_axis: self.y_axis,
+            max_texture_dimension: device.limits().max_texture_dimension_2d,
+            max_cache_size: self.max_cache_size,
+            rebuild: self.rebuild,
+            initial_cache_size: cache_size,
+            cache_generation: 0,
+            cache_stats: CacheStats::default(),
+            frame_stats: FrameStats::default(),
+            ortho: None,
+            view_matrix: matrix,
+            transform: None,
+            text_vertices: Vec::new(),
+            quads: Vec::new(),
+            vertices: Vec::new(),
+            styles: Vec::new(),
+            glyphs: Vec::new(),
+            glyph_extra: Vec::new(),
+            highlights: Vec::new(),
+            raw_vertices: Vec::new(),
+            color_atlas: color_cache_size.map(|size| {
+                ColorAtlas::new(size, self.color_emoji.is_some(), self.bitmap_strikes)
+            }),
+            color_vertices: Vec::new(),
+            staging_belt: None,
+            resident: None,
+        }
+    }
+
+    /// Builds a [`TextBrush`] rendering onto the `surface`, picking a render format
+    /// the `adapter` supports instead of guessing it, and returns it together with a
+    /// [`wgpu::SurfaceConfiguration`] of `width` and `height` using the same format.
+    /// Configure the `surface` with the returned configuration (modifying it first if
+    /// needed, without changing the format).
+    ///
+    /// Formats are picked in the following order:
+    /// 1. [`wgpu::TextureFormat::Bgra8UnormSrgb`],
+    /// 2. [`wgpu::TextureFormat::Rgba8UnormSrgb`],
+    /// 3. any other supported sRGB format,
+    /// 4. [`wgpu::TextureFormat::Bgra8Unorm`],
+    /// 5. [`wgpu::TextureFormat::Rgba8Unorm`],
+    /// 6. the first supported format.
+    ///
+    /// With sRGB formats, text is blended in linear space, so section colors should be
+    /// linear, or sRGB encoded with conversion enabled by [`Self::with_srgb()`]. When
+    /// only non-sRGB formats are supported, blending happens in sRGB space and section
+    /// colors are written as they are.
+    ///
+    /// Returns [`BrushError::IncompatibleSurface`] if the `surface` doesn't support
+    /// any format with the `adapter`.
+    pub fn build_for_surface(
+        self,
+        device: &wgpu::Device,
+        adapter: &wgpu::Adapter,
+        surface: &wgpu::Surface,
+        width: u32,
+        height: u32,
+    ) -> Result<(TextBrush<F, H>, wgpu::SurfaceConfiguration), BrushError> {
+        let capabilities = surface.get_capabilities(adapter);
+        let format = surface_format(&capabilities.formats)
+            .ok_or(BrushError::IncompatibleSurface)?;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: capabilities.alpha_modes[0],
+            view_formats: vec![],
+        };
+
+        Ok((self.build(device, width, height, format), config))
     }
 }
+
+/// Picks the render format from the supported surface `formats`, see
+/// [`BrushBuilder::build_for_surface()`].
+fn surface_format(formats: &[wgpu::TextureFormat]) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+    let preferred = |format| formats.contains(&format).then_some(format);
+
+    preferred(Bgra8UnormSrgb)
+        .or_else(|| preferred(Rgba8UnormSrgb))
+        .or_else(|| formats.iter().copied().find(|f| f.is_srgb()))
+        .or_else(|| preferred(Bgra8Unorm))
+        .or_else(|| preferred(Rgba8Unorm))
+        .or_else(|| formats.first().copied())
+}
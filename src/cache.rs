@@ -10,8 +10,12 @@ pub struct Cache {
     pub bind_group: wgpu::BindGroup,
 
     matrix_buffer: wgpu::Buffer,
+    matrix: Matrix,
     texture: wgpu::Texture,
+    /// RGBA texture of color glyphs, like emoji.
+    color_texture: wgpu::Texture,
     sampler: wgpu::Sampler,
+    filters: SamplerFilters,
 }
 
 impl Cache {
@@ -19,17 +23,12 @@ impl Cache {
         device: &wgpu::Device,
         tex_dimensions: (u32, u32),
         matrix: Matrix,
+        filters: SamplerFilters,
+        color_size: u32,
     ) -> Self {
         let texture = Self::create_cache_texture(device, tex_dimensions);
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("wgpu-text Cache Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
+        let color_texture = Self::create_color_texture(device, color_size);
+        let sampler = Self::create_sampler(device, filters);
 
         let matrix_buffer =
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -72,34 +71,37 @@ impl Cache {
                         ),
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("wgpu-text Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: matrix_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(
-                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &matrix_buffer,
+            &texture,
+            &color_texture,
+            &sampler,
+        );
 
         Self {
             matrix_buffer,
+            matrix,
             texture,
+            color_texture,
             sampler,
+            filters,
             bind_group,
             bind_group_layout,
         }
@@ -111,34 +113,63 @@ impl Cache {
         tex_dimensions: (u32, u32),
     ) {
         self.texture = Self::create_cache_texture(device, tex_dimensions);
-        self.bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("wgpu-text Bind Group"),
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: self.matrix_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(
-                        &self
-                            .texture
-                            .create_view(&wgpu::TextureViewDescriptor::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-            ],
-        });
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.matrix_buffer,
+            &self.texture,
+            &self.color_texture,
+            &self.sampler,
+        );
+    }
+
+    /// Recreates the sampler with the `filters`, unless they are unchanged.
+    pub fn set_filters(&mut self, device: &wgpu::Device, filters: SamplerFilters) {
+        if filters == self.filters {
+            return;
+        }
+        self.filters = filters;
+        self.sampler = Self::create_sampler(device, filters);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.matrix_buffer,
+            &self.texture,
+            &self.color_texture,
+            &self.sampler,
+        );
+    }
+
+    #[inline]
+    pub fn filters(&self) -> SamplerFilters {
+        self.filters
     }
 
+    /// Writes the `matrix`, unless it is equal to the current one.
     pub fn update_matrix(&mut self, matrix: Matrix, queue: &wgpu::Queue) {
+        if matrix == self.matrix {
+            return;
+        }
+        self.matrix = matrix;
         queue.write_buffer(&self.matrix_buffer, 0, bytemuck::cast_slice(&matrix));
     }
 
+    /// Sets the `matrix` without writing it, for matrices passed as push constants.
+    #[inline]
+    pub fn set_matrix(&mut self, matrix: Matrix) {
+        self.matrix = matrix;
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    #[inline]
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     pub fn update_texture(
         &mut self,
         size: Rectangle<u32>,
@@ -170,6 +201,124 @@ impl Cache {
         )
     }
 
+    /// Writes RGBA `data` of a color glyph into the color glyph texture.
+    pub fn update_color_texture(
+        &mut self,
+        size: Rectangle<u32>,
+        data: &[u8],
+        queue: &wgpu::Queue,
+    ) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.color_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: size.min[0],
+                    y: size.min[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width() * 4),
+                rows_per_image: Some(size.height()),
+            },
+            wgpu::Extent3d {
+                width: size.width(),
+                height: size.height(),
+                depth_or_array_layers: 1,
+            },
+        )
+    }
+
+    /// Records a copy of the whole cache texture into a new mappable buffer.
+    pub fn copy_texture(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> TextureReadback {
+        let (width, height) = (self.texture.width(), self.texture.height());
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = width.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wgpu-text Cache Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+
+        TextureReadback {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    fn create_sampler(device: &wgpu::Device, filters: SamplerFilters) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("wgpu-text Cache Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filters.mag,
+            min_filter: filters.min,
+            ..Default::default()
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        matrix_buffer: &wgpu::Buffer,
+        texture: &wgpu::Texture,
+        color_texture: &wgpu::Texture,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wgpu-text Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: matrix_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(
+                        &color_texture
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+            ],
+        })
+    }
+
     fn create_cache_texture(
         device: &wgpu::Device,
         dimensions: (u32, u32),
@@ -186,8 +335,224 @@ impl Cache {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Creates the `size`x`size` color glyph texture. Colors are stored sRGB encoded
+    /// and converted by the shader like vertex colors, if enabled.
+    fn create_color_texture(device: &wgpu::Device, size: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("wgpu-text Color Glyph Texture"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         })
     }
 }
+
+/// Magnification and minification filters of the cache texture sampler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerFilters {
+    pub mag: wgpu::FilterMode,
+    pub min: wgpu::FilterMode,
+}
+
+/// Cache texture copy recorded with [`TextBrush::copy_cache()`](crate::TextBrush::copy_cache),
+/// waiting to be read after its command encoder got submitted.
+#[derive(Debug)]
+pub struct TextureReadback {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl TextureReadback {
+    /// Maps the copy, blocking until it is finished, and returns one coverage byte per
+    /// texel, row by row.
+    pub fn read(self, device: &wgpu::Device) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().map_err(|_| wgpu::BufferAsyncError)??;
+
+        Ok(self.unpadded())
+    }
+
+    /// Maps the copy without blocking and returns one coverage byte per texel, row by
+    /// row, once it's finished.
+    ///
+    /// On native backends the mapping only finishes while the device is polled (e.g.
+    /// with [`wgpu::Maintain::Poll`] every frame), on the web it finishes on its own.
+    pub async fn read_async(self) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let mapping = std::sync::Arc::new(std::sync::Mutex::new(Mapping::default()));
+        let callback = mapping.clone();
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let mut mapping = callback.lock().unwrap();
+                mapping.result = Some(result);
+                if let Some(waker) = mapping.waker.take() {
+                    waker.wake();
+                }
+            });
+        MapFuture(mapping).await?;
+
+        Ok(self.unpadded())
+    }
+
+    /// Returns the mapped copy without the row padding and unmaps it.
+    fn unpadded(self) -> Vec<u8> {
+        let data = self.buffer.slice(..).get_mapped_range();
+        let mut pixels = Vec::with_capacity((self.width * self.height) as usize);
+        for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..self.width as usize]);
+        }
+        drop(data);
+        self.buffer.unmap();
+
+        pixels
+    }
+}
+
+/// Buffer mapping state shared with the `map_async` callback.
+#[derive(Default)]
+struct Mapping {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<std::task::Waker>,
+}
+
+/// Resolves once the buffer mapping of [`TextureReadback::read_async()`] finished.
+struct MapFuture(std::sync::Arc<std::sync::Mutex<Mapping>>);
+
+impl std::future::Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut mapping = self.0.lock().unwrap();
+        match mapping.result.take() {
+            Some(result) => std::task::Poll::Ready(result),
+            None => {
+                mapping.waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a device of the default adapter, or `None` if there is no adapter, in
+    /// which case the test is skipped.
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(
+            instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+        )?;
+        pollster::block_on(adapter.request_device(&Default::default(), None)).ok()
+    }
+
+    fn cache(device: &wgpu::Device, tex_dimensions: (u32, u32)) -> Cache {
+        let filters = SamplerFilters {
+            mag: wgpu::FilterMode::Linear,
+            min: wgpu::FilterMode::Linear,
+        };
+        Cache::new(device, tex_dimensions, [[0.0; 4]; 4], filters, 1)
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rectangle<u32> {
+        Rectangle {
+            min: [x, y],
+            max: [x + width, y + height],
+        }
+    }
+
+    /// Uploads distinct non-zero coverage into every rect and returns the whole
+    /// texture as expected after the uploads.
+    fn upload(
+        cache: &mut Cache,
+        queue: &wgpu::Queue,
+        rects: &[Rectangle<u32>],
+    ) -> Vec<u8> {
+        let width = cache.texture().width();
+        let mut expected = vec![0; (width * cache.texture().height()) as usize];
+        for (i, &rect) in rects.iter().enumerate() {
+            let data: Vec<u8> = (0..rect.width() * rect.height())
+                .map(|texel| (texel as u8 | 1).wrapping_add(i as u8 * 16))
+                .collect();
+            cache.update_texture(rect, &data, queue);
+            for (texel, &value) in data.iter().enumerate() {
+                let x = rect.min[0] + texel as u32 % rect.width();
+                let y = rect.min[1] + texel as u32 / rect.width();
+                expected[(y * width + x) as usize] = value;
+            }
+        }
+        expected
+    }
+
+    fn read(cache: &Cache, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<u8> {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let readback = cache.copy_texture(device, &mut encoder);
+        queue.submit([encoder.finish()]);
+        readback.read(device).unwrap()
+    }
+
+    #[test]
+    fn sub_rects_land_at_their_origin() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        // Rows aren't aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+        let mut cache = cache(&device, (67, 35));
+        let rects = [
+            rect(0, 0, 3, 2),
+            rect(64, 0, 3, 5),
+            rect(0, 32, 5, 3),
+            rect(61, 30, 6, 5),
+            rect(17, 9, 13, 7),
+            rect(30, 16, 1, 1),
+        ];
+
+        let expected = upload(&mut cache, &queue, &rects);
+
+        assert_eq!(read(&cache, &device, &queue), expected);
+    }
+
+    #[test]
+    fn uploads_after_resize_use_the_new_texture() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut cache = cache(&device, (32, 32));
+        upload(&mut cache, &queue, &[rect(0, 0, 32, 32)]);
+
+        cache.recreate_texture(&device, (96, 48));
+        let expected = upload(
+            &mut cache,
+            &queue,
+            &[rect(40, 20, 56, 28), rect(0, 0, 7, 3)],
+        );
+
+        assert_eq!(read(&cache, &device, &queue), expected);
+    }
+}
@@ -0,0 +1,74 @@
+use glyph_brush::{HorizontalAlign, Layout, Section, Text};
+
+/// Layout helper for tab-delimited tabular text, like tables and logs, where each
+/// column has its own position (tab stop) and alignment.
+///
+/// ```rust
+/// use wgpu_text::{glyph_brush::{HorizontalAlign, Text}, Columns};
+///
+/// let columns = Columns::new()
+///     .column(10.0, HorizontalAlign::Left)
+///     // Numbers end at x = 200.0.
+///     .column(200.0, HorizontalAlign::Right);
+/// let text = "apples\t12\npears\t1024";
+/// let style = Text::new("").with_scale(20.0);
+/// let sections = columns.sections(text, (0.0, 0.0), 24.0, style);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Columns {
+    columns: Vec<(f32, HorizontalAlign)>,
+}
+
+impl Columns {
+    /// Creates an empty layout, add columns with [`Self::column()`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next column at the `x` offset. Cells are aligned to `x` by their
+    /// left edge, right edge or center, depending on the `align`.
+    pub fn column(mut self, x: f32, align: HorizontalAlign) -> Self {
+        self.columns.push((x, align));
+        self
+    }
+
+    /// Lays out the tab-delimited `text` into a single line section per cell. Cell
+    /// `x` offsets are relative to `position` (top left corner) and every line of
+    /// `text` is `line_height` pixels below the previous one, empty lines included.
+    ///
+    /// Cells use the scale, font and color of the `text_style`. Tabs past the last
+    /// column are kept in the last cell.
+    pub fn sections<'a>(
+        &self,
+        text: &'a str,
+        position: (f32, f32),
+        line_height: f32,
+        text_style: Text<'a>,
+    ) -> Vec<Section<'a>> {
+        let mut sections = Vec::new();
+        if self.columns.is_empty() {
+            return sections;
+        }
+
+        for (row, line) in text.lines().enumerate() {
+            let y = position.1 + row as f32 * line_height;
+            let cells = line.splitn(self.columns.len(), '\t');
+            for (cell, &(x, align)) in cells.zip(&self.columns) {
+                if cell.is_empty() {
+                    continue;
+                }
+                sections.push(
+                    Section::default()
+                        .with_screen_position((position.0 + x, y))
+                        .with_layout(Layout::default_single_line().h_align(align))
+                        .add_text(Text {
+                            text: cell,
+                            ..text_style
+                        }),
+                );
+            }
+        }
+        sections
+    }
+}
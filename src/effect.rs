@@ -0,0 +1,477 @@
+use std::num::NonZeroU64;
+
+use wgpu::util::DeviceExt;
+
+/// Fragment shader effects. Every effect owns a slot in the effect uniform buffer,
+/// which makes it possible to draw different effects in the same frame.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectSlot {
+    Plain = 0,
+    Outlined = 1,
+    Masked = 2,
+    ImageFilled = 3,
+    Shimmer = 4,
+    Hollow = 5,
+    WithOutline = 6,
+}
+
+impl EffectSlot {
+    const COUNT: u64 = 7;
+    const ALL: [EffectSlot; Self::COUNT as usize] = [
+        EffectSlot::Plain,
+        EffectSlot::Outlined,
+        EffectSlot::Masked,
+        EffectSlot::ImageFilled,
+        EffectSlot::Shimmer,
+        EffectSlot::Hollow,
+        EffectSlot::WithOutline,
+    ];
+}
+
+/// Effect parameters as laid out in `shader.wgsl`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EffectUniform {
+    fill_color: [f32; 4],
+    outline_color: [f32; 4],
+    outline_width: f32,
+    mode: u32,
+    dilation: f32,
+    subpixel: u32,
+    mask_rect: [f32; 4],
+    time: f32,
+    band_width: f32,
+    /// Vertex and effect colors are converted from sRGB to linear if non zero.
+    srgb: u32,
+    /// Brush-wide opacity multiplier.
+    alpha: f32,
+    /// Strength and light direction (x, y) of the brush-wide emboss.
+    emboss: [f32; 4],
+    /// Colors are output with premultiplied alpha if non zero.
+    premultiplied: u32,
+    /// Exponent of the glyph coverage.
+    gamma: f32,
+    _padding: [u32; 2],
+}
+
+impl EffectUniform {
+    pub fn plain() -> Self {
+        Self {
+            fill_color: [0.0; 4],
+            outline_color: [0.0; 4],
+            outline_width: 0.0,
+            mode: EffectSlot::Plain as u32,
+            dilation: 0.0,
+            subpixel: 0,
+            mask_rect: [0.0; 4],
+            time: 0.0,
+            band_width: 0.0,
+            srgb: 0,
+            alpha: 1.0,
+            emboss: [0.0; 4],
+            premultiplied: 0,
+            gamma: 1.0,
+            _padding: [0; 2],
+        }
+    }
+
+    pub fn outlined(fill_color: [f32; 4], outline_color: [f32; 4], width: f32) -> Self {
+        Self {
+            fill_color,
+            outline_color,
+            outline_width: width.max(0.0),
+            mode: EffectSlot::Outlined as u32,
+            ..Self::plain()
+        }
+    }
+
+    pub fn hollow(outline_color: [f32; 4], width: f32) -> Self {
+        Self {
+            outline_color,
+            outline_width: width.max(0.0),
+            mode: EffectSlot::Hollow as u32,
+            ..Self::plain()
+        }
+    }
+
+    /// Like [`EffectUniform::outlined`], but glyphs keep their own colors.
+    pub fn with_outline(outline_color: [f32; 4], width: f32) -> Self {
+        Self {
+            outline_color,
+            outline_width: width.max(0.0),
+            mode: EffectSlot::WithOutline as u32,
+            ..Self::plain()
+        }
+    }
+
+    /// `mask_rect` is in text coordinates (min.xy, max.xy).
+    pub fn masked(mask_rect: [f32; 4]) -> Self {
+        Self {
+            mask_rect,
+            mode: EffectSlot::Masked as u32,
+            ..Self::plain()
+        }
+    }
+
+    /// `fill_rect` is in text coordinates (min.xy, max.xy).
+    pub fn image_filled(fill_rect: [f32; 4], tint: [f32; 4]) -> Self {
+        Self {
+            fill_color: tint,
+            mask_rect: fill_rect,
+            mode: EffectSlot::ImageFilled as u32,
+            ..Self::plain()
+        }
+    }
+
+    /// `bounds` are the text bounds in text coordinates (min.xy, max.xy).
+    pub fn shimmer(
+        bounds: [f32; 4],
+        base_color: [f32; 4],
+        highlight_color: [f32; 4],
+        time: f32,
+        width: f32,
+    ) -> Self {
+        Self {
+            fill_color: base_color,
+            outline_color: highlight_color,
+            mask_rect: bounds,
+            time,
+            band_width: width.max(0.0),
+            mode: EffectSlot::Shimmer as u32,
+            ..Self::plain()
+        }
+    }
+}
+
+/// Responsible for the effect uniform buffer, bound with a dynamic offset per draw.
+#[derive(Debug)]
+pub struct Effects {
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+
+    buffer: wgpu::Buffer,
+    stride: u64,
+    /// Last written parameters of every slot.
+    uniforms: [EffectUniform; EffectSlot::COUNT as usize],
+}
+
+impl Effects {
+    /// `subpixel` enables horizontally supersampled glyph coordinates for all slots,
+    /// `srgb` the conversion of sRGB colors to linear ones, `premultiplied` the
+    /// premultiplied alpha output and `gamma` is the glyph coverage exponent.
+    pub fn new(
+        device: &wgpu::Device,
+        subpixel: bool,
+        srgb: bool,
+        premultiplied: bool,
+        gamma: f32,
+    ) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let size = std::mem::size_of::<EffectUniform>() as u64;
+        let stride = size.div_ceil(alignment) * alignment;
+
+        let uniforms = [EffectUniform {
+            subpixel: subpixel as u32,
+            srgb: srgb as u32,
+            premultiplied: premultiplied as u32,
+            gamma,
+            ..EffectUniform::plain()
+        }; EffectSlot::COUNT as usize];
+        let mut contents = vec![0u8; (stride * EffectSlot::COUNT) as usize];
+        for (slot, uniform) in contents.chunks_exact_mut(stride as usize).zip(&uniforms) {
+            slot[..size as usize].copy_from_slice(bytemuck::bytes_of(uniform));
+        }
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-text Effect Buffer"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("wgpu-text Effect Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(size),
+                    },
+                    count: None,
+                }],
+            });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wgpu-text Effect Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(size),
+                }),
+            }],
+        });
+
+        Self {
+            bind_group_layout,
+            bind_group,
+            buffer,
+            stride,
+            uniforms,
+        }
+    }
+
+    #[inline]
+    pub fn offset(&self, slot: EffectSlot) -> u32 {
+        (slot as u64 * self.stride) as u32
+    }
+
+    /// Writes `effect` into `slot`, keeping the brush-wide dilation, subpixel, sRGB and
+    /// premultiplied alpha flags, alpha, emboss and gamma. Effect colors are converted
+    /// to linear ones if the sRGB flag is set.
+    pub fn update(
+        &mut self,
+        slot: EffectSlot,
+        effect: EffectUniform,
+        queue: &wgpu::Queue,
+    ) {
+        let EffectUniform {
+            dilation,
+            subpixel,
+            srgb,
+            alpha,
+            emboss,
+            premultiplied,
+            gamma,
+            ..
+        } = self.uniforms[slot as usize];
+        let color = |color: [f32; 4]| match srgb != 0 {
+            true => srgb_to_linear(color),
+            false => color,
+        };
+        self.uniforms[slot as usize] = EffectUniform {
+            fill_color: color(effect.fill_color),
+            outline_color: color(effect.outline_color),
+            dilation,
+            subpixel,
+            srgb,
+            alpha,
+            emboss,
+            premultiplied,
+            gamma,
+            ..effect
+        };
+        self.write(slot, queue);
+    }
+
+    /// Writes the `bounds` (the mask or text rectangle) of `slot` if they changed,
+    /// keeping its other parameters.
+    pub fn update_bounds(
+        &mut self,
+        slot: EffectSlot,
+        bounds: [f32; 4],
+        queue: &wgpu::Queue,
+    ) {
+        if self.uniforms[slot as usize].mask_rect != bounds {
+            self.uniforms[slot as usize].mask_rect = bounds;
+            self.write(slot, queue);
+        }
+    }
+
+    /// Sets the coverage dilation radius (in cache texels) of all slots.
+    pub fn set_dilation(&mut self, radius: f32, queue: &wgpu::Queue) {
+        for slot in EffectSlot::ALL {
+            self.uniforms[slot as usize].dilation = radius.max(0.0);
+            self.write(slot, queue);
+        }
+    }
+
+    /// Sets the opacity multiplier of all slots.
+    pub fn set_alpha(&mut self, alpha: f32, queue: &wgpu::Queue) {
+        for slot in EffectSlot::ALL {
+            self.uniforms[slot as usize].alpha = alpha.clamp(0.0, 1.0);
+            self.write(slot, queue);
+        }
+    }
+
+    /// Sets the emboss `strength` and `light_dir` of all slots.
+    pub fn set_emboss(
+        &mut self,
+        strength: f32,
+        light_dir: [f32; 2],
+        queue: &wgpu::Queue,
+    ) {
+        for slot in EffectSlot::ALL {
+            self.uniforms[slot as usize].emboss =
+                [strength, light_dir[0], light_dir[1], 0.0];
+            self.write(slot, queue);
+        }
+    }
+
+    #[inline]
+    pub fn dilation(&self) -> f32 {
+        self.uniforms[0].dilation
+    }
+
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        self.uniforms[0].alpha
+    }
+
+    fn write(&self, slot: EffectSlot, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.buffer,
+            self.offset(slot) as u64,
+            bytemuck::bytes_of(&self.uniforms[slot as usize]),
+        );
+    }
+}
+
+/// Converts the color channels of the sRGB encoded `color` to linear ones, keeping
+/// its alpha.
+fn srgb_to_linear([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    let convert = |c: f32| match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    };
+    [convert(r), convert(g), convert(b), a]
+}
+
+/// Responsible for the texture bindings used by masked and image filled drawing,
+/// each drawn with its own texture.
+#[derive(Debug)]
+pub struct Mask {
+    pub bind_group_layout: wgpu::BindGroupLayout,
+
+    sampler: wgpu::Sampler,
+    /// Bound while no texture was provided yet, never sampled.
+    empty: wgpu::BindGroup,
+    masked: Option<wgpu::BindGroup>,
+    image_filled: Option<wgpu::BindGroup>,
+}
+
+impl Mask {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("wgpu-text Mask Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(
+                            wgpu::SamplerBindingType::Filtering,
+                        ),
+                        count: None,
+                    },
+                ],
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("wgpu-text Mask Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("wgpu-text Empty Mask Texture"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let empty = create_mask_bind_group(
+            device,
+            &bind_group_layout,
+            &sampler,
+            &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+
+        Self {
+            bind_group_layout,
+            sampler,
+            empty,
+            masked: None,
+            image_filled: None,
+        }
+    }
+
+    /// Sets the texture of the [`EffectSlot::Masked`] or [`EffectSlot::ImageFilled`]
+    /// `slot`, other slots don't sample any texture.
+    pub fn set(
+        &mut self,
+        device: &wgpu::Device,
+        slot: EffectSlot,
+        view: &wgpu::TextureView,
+    ) {
+        let bind_group = Some(create_mask_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            view,
+        ));
+        match slot {
+            EffectSlot::Masked => self.masked = bind_group,
+            EffectSlot::ImageFilled => self.image_filled = bind_group,
+            _ => {}
+        }
+    }
+
+    #[inline]
+    pub fn bind_group(&self, slot: EffectSlot) -> &wgpu::BindGroup {
+        let current = match slot {
+            EffectSlot::Masked => self.masked.as_ref(),
+            EffectSlot::ImageFilled => self.image_filled.as_ref(),
+            _ => None,
+        };
+        current.unwrap_or(&self.empty)
+    }
+}
+
+fn create_mask_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("wgpu-text Mask Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+
+use glyph_brush::{
+    ab_glyph::{point, Font, GlyphId, GlyphImageFormat, Rect},
+    FontId, Rectangle,
+};
+
+/// Texture coordinates of color glyphs are offset by this value, which marks them for
+/// the shader (like the negative coordinates of solid quads).
+pub(crate) const COLOR_TEX_OFFSET: f32 = 2.0;
+
+/// Cache of color glyph images (like emoji) and embedded bitmap strikes packed into a
+/// square RGBA texture, separate from the coverage cache texture of **glyph_brush**.
+///
+/// Glyphs are packed in rows. When a glyph doesn't fit anymore, the whole atlas is
+/// cleared by the next [`ColorAtlas::begin_frame()`], so only glyphs of the frames
+/// after it are cached again.
+#[derive(Debug)]
+pub(crate) struct ColorAtlas {
+    size: u32,
+    /// Color images are drawn at any size, scaled from the largest strike.
+    color_glyphs: bool,
+    /// Bitmap strikes are drawn at their own size, see [`strike_size()`].
+    strikes: bool,
+    /// Cached glyphs by the strike size, `u16::MAX` for the largest one, `None` for
+    /// glyphs without a supported image.
+    glyphs: HashMap<(FontId, GlyphId, u16), Option<ColorGlyph>>,
+    /// Top left corner of the next glyph and the height of the current row.
+    cursor: (u32, u32),
+    row_height: u32,
+    full: bool,
+    /// Texture writes of newly cached glyphs, waiting for upload.
+    updates: Vec<(Rectangle<u32>, Vec<u8>)>,
+}
+
+/// Color glyph cached in a [`ColorAtlas`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ColorGlyph {
+    /// Texture coordinates, offset by [`COLOR_TEX_OFFSET`].
+    pub tex_coords: Rect,
+    /// Image rectangle relative to the glyph origin, in pixels of the image strike.
+    pub rect: Rect,
+    /// Font size in pixels of the image strike.
+    pub pixels_per_em: f32,
+    /// `false` for grayscale and monochrome strikes, drawn in the text color.
+    pub colored: bool,
+}
+
+impl ColorAtlas {
+    pub fn new(size: u32, color_glyphs: bool, strikes: bool) -> Self {
+        Self {
+            size,
+            color_glyphs,
+            strikes,
+            glyphs: HashMap::new(),
+            cursor: (0, 0),
+            row_height: 0,
+            full: false,
+            updates: Vec::new(),
+        }
+    }
+
+    /// Clears the atlas if a glyph didn't fit into it since the last call.
+    pub fn begin_frame(&mut self) {
+        if self.full {
+            self.clear();
+        }
+    }
+
+    /// Drops all cached glyphs, e.g. after their fonts changed.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.cursor = (0, 0);
+        self.row_height = 0;
+        self.full = false;
+    }
+
+    /// Takes the texture writes of the glyphs cached since the last call.
+    #[inline]
+    pub fn take_updates(&mut self) -> Vec<(Rectangle<u32>, Vec<u8>)> {
+        std::mem::take(&mut self.updates)
+    }
+
+    /// Returns the cached image of the glyph drawn at `pixel_size`, caching it first if
+    /// needed: the bitmap strike of that size if strikes are enabled, or else the color
+    /// image if color glyphs are enabled. Glyphs without such an image and glyphs not
+    /// fitting into the atlas return `None`.
+    pub fn get<F: Font>(
+        &mut self,
+        font: &F,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        pixel_size: f32,
+    ) -> Option<ColorGlyph> {
+        let strike = match self.strikes {
+            true => strike_size(pixel_size)
+                .and_then(|size| self.get_strike(font, font_id, glyph_id, size)),
+            false => None,
+        };
+        match self.color_glyphs {
+            true => strike.or_else(|| self.get_strike(font, font_id, glyph_id, u16::MAX)),
+            false => strike,
+        }
+    }
+
+    /// Returns the cached image of the strike of `size` pixels per em, or of the
+    /// largest color strike (scaled down when drawn) for `u16::MAX`.
+    fn get_strike<F: Font>(
+        &mut self,
+        font: &F,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        size: u16,
+    ) -> Option<ColorGlyph> {
+        let key = (font_id, glyph_id, size);
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+
+        let image = font.glyph_raster_image2(glyph_id, size).filter(|image| {
+            match size {
+                u16::MAX => is_color(&image.format),
+                // Fonts return the closest strike if there's none of `size`.
+                _ => image.pixels_per_em == size,
+            }
+        });
+        let decoded = image.as_ref().and_then(|image| {
+            rgba_pixels(&image.format, image.data, image.width, image.height)
+        });
+        let (image, (width, height, pixels)) = match (image, decoded) {
+            (Some(image), Some(decoded)) => (image, decoded),
+            _ => {
+                self.glyphs.insert(key, None);
+                return None;
+            }
+        };
+
+        let (x, y) = match self.allocate(width, height) {
+            Some(position) => position,
+            None => {
+                if !self.full {
+                    log::warn!(
+                        "wgpu-text: The color glyph texture is full, it's cleared by \
+                        the next queue. Build the brush with a bigger color glyph \
+                        texture if this keeps happening."
+                    );
+                }
+                self.full = true;
+                return None;
+            }
+        };
+        self.updates.push((
+            Rectangle {
+                min: [x, y],
+                max: [x + width, y + height],
+            },
+            pixels,
+        ));
+
+        let size = self.size as f32;
+        // Image origin is its bottom left corner, above the baseline.
+        let (left, bottom) = (image.origin.x, image.origin.y);
+        let glyph = ColorGlyph {
+            tex_coords: Rect {
+                min: point(
+                    x as f32 / size + COLOR_TEX_OFFSET,
+                    y as f32 / size + COLOR_TEX_OFFSET,
+                ),
+                max: point(
+                    (x + width) as f32 / size + COLOR_TEX_OFFSET,
+                    (y + height) as f32 / size + COLOR_TEX_OFFSET,
+                ),
+            },
+            rect: Rect {
+                min: point(left, -(bottom + height as f32)),
+                max: point(left + width as f32, -bottom),
+            },
+            pixels_per_em: image.pixels_per_em.max(1) as f32,
+            colored: is_color(&image.format),
+        };
+        self.glyphs.insert(key, Some(glyph));
+        Some(glyph)
+    }
+
+    /// Finds space for an image in the current or a new row, keeping a one texel gap
+    /// between images against filtering bleed.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if self.full || width + 1 > self.size || height + 1 > self.size {
+            return None;
+        }
+        if self.cursor.0 + width + 1 > self.size {
+            self.cursor = (0, self.cursor.1 + self.row_height);
+            self.row_height = 0;
+        }
+        if self.cursor.1 + height + 1 > self.size {
+            return None;
+        }
+        let position = self.cursor;
+        self.cursor.0 += width + 1;
+        self.row_height = self.row_height.max(height + 1);
+        Some(position)
+    }
+}
+
+/// Returns the strike size (pixels per em) matching glyphs drawn at `pixel_size`,
+/// `None` for fractional sizes, which no strike matches.
+fn strike_size(pixel_size: f32) -> Option<u16> {
+    let size = pixel_size.round();
+    let matching = (pixel_size - size).abs() < 0.01 && size >= 1.0;
+    (matching && size < u16::MAX as f32).then_some(size as u16)
+}
+
+/// Returns `true` for color image formats, `false` for grayscale and monochrome ones.
+#[inline]
+fn is_color(format: &GlyphImageFormat) -> bool {
+    matches!(
+        format,
+        GlyphImageFormat::Png | GlyphImageFormat::BitmapPremulBgra32
+    )
+}
+
+/// Converts glyph image `data` to straight alpha RGBA pixels, `None` for unsupported
+/// formats. Grayscale and monochrome bitmaps become white pixels with their coverage
+/// as alpha. PNG images need the `emoji` feature.
+fn rgba_pixels(
+    format: &GlyphImageFormat,
+    data: &[u8],
+    width: u16,
+    height: u16,
+) -> Option<(u32, u32, Vec<u8>)> {
+    match format {
+        #[cfg(feature = "emoji")]
+        GlyphImageFormat::Png => decode_png(data),
+        GlyphImageFormat::BitmapPremulBgra32 => {
+            let (width, height) = (width as u32, height as u32);
+            if data.len() < (width * height * 4) as usize {
+                return None;
+            }
+            let pixels = data
+                .chunks_exact(4)
+                .take((width * height) as usize)
+                .flat_map(|bgra| {
+                    let alpha = bgra[3] as u32;
+                    let straight = |c: u8| match alpha {
+                        0 => 0,
+                        _ => (c as u32 * 255 / alpha).min(255) as u8,
+                    };
+                    [
+                        straight(bgra[2]),
+                        straight(bgra[1]),
+                        straight(bgra[0]),
+                        bgra[3],
+                    ]
+                })
+                .collect();
+            Some((width, height, pixels))
+        }
+        GlyphImageFormat::BitmapMono => gray_pixels(data, width, height, 1, true),
+        GlyphImageFormat::BitmapMonoPacked => gray_pixels(data, width, height, 1, false),
+        GlyphImageFormat::BitmapGray2 => gray_pixels(data, width, height, 2, true),
+        GlyphImageFormat::BitmapGray2Packed => gray_pixels(data, width, height, 2, false),
+        GlyphImageFormat::BitmapGray4 => gray_pixels(data, width, height, 4, true),
+        GlyphImageFormat::BitmapGray4Packed => gray_pixels(data, width, height, 4, false),
+        GlyphImageFormat::BitmapGray8 => gray_pixels(data, width, height, 8, true),
+        _ => None,
+    }
+}
+
+/// Expands a bitmap of `bits` per pixel, the most significant bits first, to white
+/// RGBA pixels with the bitmap values as alpha. Rows of `padded` bitmaps start at a
+/// new byte.
+fn gray_pixels(
+    data: &[u8],
+    width: u16,
+    height: u16,
+    bits: u32,
+    padded: bool,
+) -> Option<(u32, u32, Vec<u8>)> {
+    let (width, height) = (width as u32, height as u32);
+    let row_bits = match padded {
+        true => (width * bits).div_ceil(8) * 8,
+        false => width * bits,
+    };
+    if (data.len() as u64) * 8 < row_bits as u64 * height as u64 {
+        return None;
+    }
+    let max = (1 << bits) - 1;
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| y * row_bits + x * bits))
+        .flat_map(|bit| {
+            let byte = data[(bit / 8) as usize] as u32;
+            let value = (byte >> (8 - bits - bit % 8)) & max;
+            [255, 255, 255, (value * 255 / max) as u8]
+        })
+        .collect();
+    Some((width, height, pixels))
+}
+
+/// Decodes a PNG image, like the ones of `CBDT` emoji fonts, to RGBA pixels.
+#[cfg(feature = "emoji")]
+fn decode_png(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let data = &buffer[..info.buffer_size()];
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => data.to_vec(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        // Expanded to RGB(A) by the transformations.
+        png::ColorType::Indexed => return None,
+    };
+    Some((info.width, info.height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premultiplied_bgra_to_straight_rgba() {
+        let data = [0, 0, 128, 128, 255, 0, 0, 255, 0, 0, 0, 0, 10, 20, 30, 255];
+        let (width, height, pixels) =
+            rgba_pixels(&GlyphImageFormat::BitmapPremulBgra32, &data, 2, 2).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(
+            pixels,
+            [255, 0, 0, 128, 0, 0, 255, 255, 0, 0, 0, 0, 30, 20, 10, 255]
+        );
+    }
+
+    #[test]
+    fn truncated_bitmap_is_rejected() {
+        let data = [0; 12];
+        assert!(
+            rgba_pixels(&GlyphImageFormat::BitmapPremulBgra32, &data, 2, 2).is_none()
+        );
+    }
+
+    #[test]
+    fn mono_bitmap_rows_are_padded() {
+        // 3x2, the rows `101` and `011` padded to a byte each.
+        let data = [0b1010_0000, 0b0110_0000];
+        let (_, _, pixels) =
+            rgba_pixels(&GlyphImageFormat::BitmapMono, &data, 3, 2).unwrap();
+        let alpha: Vec<u8> = pixels.chunks_exact(4).map(|p| p[3]).collect();
+        assert_eq!(alpha, [255, 0, 255, 0, 255, 255]);
+        assert!(pixels.chunks_exact(4).all(|p| p[..3] == [255; 3]));
+    }
+
+    #[test]
+    fn packed_gray_bitmap_is_expanded() {
+        // 3x2 with 2 bits per pixel, the second row continuing in the first byte.
+        let data = [0b00_01_10_11, 0b11_10_00_00];
+        let (_, _, pixels) =
+            rgba_pixels(&GlyphImageFormat::BitmapGray2Packed, &data, 3, 2).unwrap();
+        let alpha: Vec<u8> = pixels.chunks_exact(4).map(|p| p[3]).collect();
+        assert_eq!(alpha, [0, 85, 170, 255, 255, 170]);
+        assert!(
+            rgba_pixels(&GlyphImageFormat::BitmapGray2Packed, &data[..1], 3, 2).is_none()
+        );
+    }
+
+    #[test]
+    fn strikes_match_whole_pixel_sizes() {
+        assert_eq!(strike_size(16.0), Some(16));
+        assert_eq!(strike_size(15.999), Some(16));
+        assert_eq!(strike_size(16.5), None);
+        assert_eq!(strike_size(0.0), None);
+        assert_eq!(strike_size(1e6), None);
+    }
+
+    /// 2x2 RGBA PNG: red, half transparent green, blue and a transparent pixel.
+    #[cfg(feature = "emoji")]
+    const PNG_GLYPH: [u8; 79] = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49,
+        0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06,
+        0x00, 0x00, 0x00, 0x72, 0xb6, 0x0d, 0x24, 0x00, 0x00, 0x00, 0x16, 0x49, 0x44,
+        0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xf0, 0x1f, 0x08, 0x1b, 0x18,
+        0x80, 0x34, 0x90, 0xcd, 0xc0, 0x00, 0x00, 0x3a, 0xdc, 0x05, 0x7c, 0x7f, 0xd7,
+        0x23, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60,
+        0x82,
+    ];
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn png_glyph_is_decoded() {
+        let (width, height, pixels) =
+            rgba_pixels(&GlyphImageFormat::Png, &PNG_GLYPH, 2, 2).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(
+            pixels,
+            [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 255, 0, 0, 0, 0]
+        );
+    }
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn corrupt_png_is_rejected() {
+        assert!(rgba_pixels(&GlyphImageFormat::Png, &PNG_GLYPH[..40], 2, 2).is_none());
+    }
+
+    #[test]
+    fn atlas_packs_rows_and_clears_when_full() {
+        let mut atlas = ColorAtlas::new(8, true, false);
+        assert_eq!(atlas.allocate(3, 3), Some((0, 0)));
+        assert_eq!(atlas.allocate(3, 3), Some((4, 0)));
+        assert_eq!(atlas.allocate(3, 3), Some((0, 4)));
+        assert_eq!(atlas.allocate(3, 4), None);
+        // Set by `get()` when an image doesn't fit.
+        atlas.full = true;
+        assert_eq!(atlas.allocate(1, 1), None);
+        atlas.begin_frame();
+        assert_eq!(atlas.allocate(3, 3), Some((0, 0)));
+    }
+}
@@ -1,10 +1,27 @@
 use std::{error::Error, fmt::Display};
 
+use glyph_brush::FontId;
+
 /// Result of `TextBrush` errors and problems.
 #[derive(Debug)]
 pub enum BrushError {
     /// Cache texture exceeded the limitations stated in `wgpu::Device`.
     TooBigCacheTexture(u32),
+    /// None of the fonts has the requested variation axis (contains the axis tag).
+    UnknownVariationAxis([u8; 4]),
+    /// The surface supports no texture format with the adapter.
+    IncompatibleSurface,
+    /// Cache texture would have to grow beyond the maximum dimensions (width, height)
+    /// set with `BrushBuilder::with_max_cache_size()`.
+    CacheSizeExceeded(u32, u32),
+    /// Section (index in the queued sections) uses a font id the brush has no font
+    /// for.
+    UnknownFont(usize, FontId),
+    /// Glyph queued with `TextBrush::queue_positioned()` (or `queue_glyph()` and
+    /// `queue_shaped()`) uses a font id the brush has no font for.
+    UnknownGlyphFont(FontId),
+    /// Font id passed to `TextBrush::replace_font()` isn't a font of the brush.
+    UnknownReplacedFont(FontId),
 }
 
 impl Error for BrushError {}
@@ -24,6 +41,42 @@ impl Display for BrushError {
                 texture dimensions.",
                 dimensions
             ),
+            BrushError::UnknownVariationAxis(tag) => write!(
+                f,
+                "None of the provided fonts has the '{}' variation axis! \
+                Check the available axes with ab_glyph::VariableFont::variations().",
+                String::from_utf8_lossy(tag)
+            ),
+            BrushError::IncompatibleSurface => write!(
+                f,
+                "The surface doesn't support any texture format with the \
+                provided adapter!"
+            ),
+            BrushError::CacheSizeExceeded(width, height) => write!(
+                f,
+                "The cache texture can't grow beyond the maximum size of {}x{} \
+                set with BrushBuilder::with_max_cache_size()! Queue less text at \
+                once or allow a bigger cache texture.",
+                width, height
+            ),
+            BrushError::UnknownFont(section, font_id) => write!(
+                f,
+                "Section {} uses the font id {}, which isn't a font of this \
+                brush! Add the font with TextBrush::add_font() first.",
+                section, font_id.0
+            ),
+            BrushError::UnknownGlyphFont(font_id) => write!(
+                f,
+                "A queued glyph uses the font id {}, which isn't a font of this \
+                brush! Add the font with TextBrush::add_font() first.",
+                font_id.0
+            ),
+            BrushError::UnknownReplacedFont(font_id) => write!(
+                f,
+                "Can't replace the font with id {}, which isn't a font of this \
+                brush! Add the font with TextBrush::add_font() instead.",
+                font_id.0
+            ),
         }
     }
 }
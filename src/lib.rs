@@ -13,19 +13,74 @@
 //! To learn about GPU texture caching, see
 //! [`caching behaviour`](https://docs.rs/glyph_brush/latest/glyph_brush/struct.GlyphBrush.html#caching-behaviour)
 //!
+//! Text layout doesn't do OpenType shaping, so combining marks aren't positioned by the
+//! font's `GPOS` table. Glyphs positioned by an external shaper can be queued with
+//! [`TextBrush::queue_positioned()`]. With the `shaping` feature, runs shaped by
+//! **[rustybuzz](https://github.com/RazrFalcon/rustybuzz)** can be converted into a
+//! [`ShapedRun`] and queued with [`TextBrush::queue_shaped()`].
+//!
+//! All sections queued in a [`TextBrush`] are drawn with a single instanced draw call
+//! from one cache texture, whatever their fonts. Prefer one brush with several fonts
+//! (see [`BrushBuilder::using_fonts()`] and [`TextBrush::add_font()`]) and section
+//! options over several brushes, which can't share their cache textures and each need
+//! their own draw call.
+//!
+//! Color bitmap emoji are drawn in full color by brushes built with
+//! [`BrushBuilder::with_color_emoji()`], PNG emoji (like the ones of `CBDT` fonts) need
+//! the `emoji` feature.
+//!
 //! > Look trough [`examples`](https://github.com/Blatko1/wgpu_text/tree/master/examples).
 
 mod brush;
 mod cache;
+mod columns;
+mod effect;
+mod emoji;
 mod error;
 mod pipeline;
+mod section;
+mod shaping;
 
-pub use brush::{BrushBuilder, TextBrush};
+pub use brush::{
+    BrushBuilder, CacheStats, FrameStats, GlyphRect, GrowthPolicy, Hinting, LineMetrics,
+    PreparedText, StaticText, TextBrush,
+};
+pub use cache::TextureReadback;
+pub use columns::Columns;
 pub use glyph_brush;
+pub use pipeline::{BlendMode, Vertex, VertexStyle};
+pub use section::{
+    FadeEdge, GradientDirection, LayoutMode, SectionExt, StyledSection, VerticalRunAlign,
+};
+pub use shaping::ShapedRun;
 
 /// Represents a two-dimensional array matrix with 4x4 dimensions.
 pub type Matrix = [[f32; 4]; 4];
 
+/// Direction of the y axis of the pixel coordinates used by a [`TextBrush`], see
+/// [`BrushBuilder::with_y_axis()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum YAxis {
+    /// Origin at the top left corner, y grows downwards.
+    #[default]
+    Down,
+    /// Origin at the bottom left corner, y grows upwards (OpenGL-style).
+    Up,
+}
+
+impl YAxis {
+    /// Creates an orthographic matrix with given dimensions `width` and `height` and
+    /// the origin at the corner of this axis. Equal to [`ortho()`] for [`YAxis::Down`].
+    pub fn ortho(self, width: f32, height: f32) -> Matrix {
+        let mut matrix = ortho(width, height);
+        if self == YAxis::Up {
+            matrix[1][1] = -matrix[1][1];
+            matrix[3][1] = -1.0;
+        }
+        matrix
+    }
+}
+
 /// Creates an orthographic matrix with given dimensions `width` and `height`.
 #[rustfmt::skip]
 pub fn ortho(width: f32, height: f32) -> Matrix {
@@ -36,3 +91,19 @@ pub fn ortho(width: f32, height: f32) -> Matrix {
         [-1.0,        1.0,          0.0, 1.0]
     ]
 }
+
+/// Creates an orthographic matrix with given dimensions `width` and `height`, which
+/// also maps glyph *z* values (see [`BrushBuilder::with_depth_stencil()`]) from the
+/// `near..far` range to the `0.0..1.0` depth range.
+///
+/// With `near` of `0.0` and `far` of `1.0`, it's equal to [`ortho()`].
+#[rustfmt::skip]
+pub fn ortho_with_depth(width: f32, height: f32, near: f32, far: f32) -> Matrix {
+    let depth = 1.0 / (far - near);
+    [
+        [2.0 / width, 0.0,          0.0,           0.0],
+        [0.0,        -2.0 / height, 0.0,           0.0],
+        [0.0,         0.0,          depth,         0.0],
+        [-1.0,        1.0,          -near * depth, 1.0]
+    ]
+}
@@ -1,128 +1,613 @@
-use std::num::NonZeroU32;
+use std::{
+    num::{NonZeroU32, NonZeroU64},
+    ops::Range,
+};
 
 use glyph_brush::{
     ab_glyph::{point, Rect},
     Rectangle,
 };
-use wgpu::util::DeviceExt;
 
-use crate::{cache::Cache, Matrix};
+use crate::{
+    cache::{Cache, SamplerFilters, TextureReadback},
+    effect::{EffectSlot, EffectUniform, Effects, Mask},
+    section::{GlyphExtra, GradientDirection},
+    Matrix,
+};
+
+/// Preset blend modes used for blending text with the render target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Straight alpha blending ([`wgpu::BlendState::ALPHA_BLENDING`]).
+    #[default]
+    Alpha,
+    /// Adds the text color, scaled by its alpha, on top of the render target.
+    /// Useful for glow effects.
+    Additive,
+    /// Any other blend state, e.g. for compositing onto HDR targets.
+    Custom(wgpu::BlendState),
+}
+
+impl BlendMode {
+    /// Returns the [`wgpu::BlendState`] this mode represents.
+    pub fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendMode::Custom(state) => state,
+        }
+    }
+}
+
+/// Render pipeline properties, kept for recreating the inner render pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    pub render_format: wgpu::TextureFormat,
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+    pub multisample: wgpu::MultisampleState,
+    pub multiview: Option<NonZeroU32>,
+    pub blend_mode: BlendMode,
+    pub subpixel: bool,
+    /// Converts sRGB encoded colors to linear ones before writing them.
+    pub srgb: bool,
+    /// Outputs colors with premultiplied alpha, blended accordingly.
+    pub premultiplied: bool,
+    /// Pushes the matrix as a push constant with every draw instead of writing it
+    /// into the uniform buffer.
+    pub push_constants: bool,
+    /// Usages of the vertex buffer besides `VERTEX | COPY_DST`.
+    pub vertex_usage: wgpu::BufferUsages,
+    /// Exponent of the glyph coverage.
+    pub gamma: f32,
+    /// Width and height of the color glyph texture.
+    pub color_cache_size: u32,
+}
+
+impl PipelineConfig {
+    /// Returns the blend state of the current blend mode, adjusted for premultiplied
+    /// alpha output. Custom blend states are used unchanged.
+    fn blend_state(&self) -> wgpu::BlendState {
+        match (self.premultiplied, self.blend_mode) {
+            (true, BlendMode::Alpha) => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            (true, BlendMode::Additive) => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    ..self.blend_mode.blend_state().color
+                },
+                ..self.blend_mode.blend_state()
+            },
+            _ => self.blend_mode.blend_state(),
+        }
+    }
+}
+
+/// Render pipeline reading only [`Vertex`] instances and its variant also reading
+/// their [`VertexStyle`], created once styles are used.
+#[derive(Debug)]
+struct Variants {
+    plain: wgpu::RenderPipeline,
+    styled: Option<wgpu::RenderPipeline>,
+}
+
+impl Variants {
+    #[inline]
+    fn get(&self, styled: bool) -> &wgpu::RenderPipeline {
+        match (styled, &self.styled) {
+            (true, Some(pipeline)) => pipeline,
+            _ => &self.plain,
+        }
+    }
+}
 
 /// Responsible for drawing text.
 #[derive(Debug)]
 pub struct Pipeline {
-    inner: wgpu::RenderPipeline,
+    /// Render pipelines created so far, one per used blend mode.
+    pipelines: Vec<(BlendMode, Variants)>,
+    current: usize,
+    /// Coverage masking and color adding pipelines of subpixel drawing.
+    subpixel: Option<[Variants; 2]>,
+    /// Styled pipeline variants are created, also for blend modes used later.
+    styled: bool,
+    shader: wgpu::ShaderModule,
+    /// User provided module with the `fs_main` entry point, replacing the built-in one.
+    fragment_shader: Option<wgpu::ShaderModule>,
+    layout: wgpu::PipelineLayout,
+    config: PipelineConfig,
+
     cache: Cache,
+    effects: Effects,
+    mask: Mask,
 
     vertex_buffer: wgpu::Buffer,
     vertex_buffer_len: usize,
     vertices: u32,
+    /// Buffer of the vertex styles, created when styles are first uploaded.
+    style_buffer: Option<wgpu::Buffer>,
+    style_buffer_len: usize,
+    /// The uploaded vertices have styles.
+    styles: bool,
 }
 
 impl Pipeline {
     pub fn new(
         device: &wgpu::Device,
-        render_format: wgpu::TextureFormat,
-        depth_stencil: Option<wgpu::DepthStencilState>,
-        multisample: wgpu::MultisampleState,
-        multiview: Option<NonZeroU32>,
+        config: PipelineConfig,
         tex_dimensions: (u32, u32),
         matrix: Matrix,
+        filters: SamplerFilters,
+        vertex_capacity: usize,
+        fragment_shader: Option<wgpu::ShaderModuleDescriptor>,
     ) -> Pipeline {
-        let cache = Cache::new(device, tex_dimensions, matrix);
+        let cache = Cache::new(
+            device,
+            tex_dimensions,
+            matrix,
+            filters,
+            config.color_cache_size,
+        );
+        let effects = Effects::new(
+            device,
+            config.subpixel,
+            config.srgb,
+            config.premultiplied,
+            config.gamma,
+        );
+        let mask = Mask::new(device);
 
-        let shader =
-            device.create_shader_module(wgpu::include_wgsl!("shader/shader.wgsl"));
-
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("wgpu-text Vertex Buffer"),
-            size: 0,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        let (label, matrix) = match config.push_constants {
+            true => ("wgpu-text Push Constant Shader", PUSH_CONSTANT_MATRIX),
+            false => ("wgpu-text Shader", UNIFORM_MATRIX),
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl([matrix, SHADER].concat().into()),
         });
+        let fragment_shader =
+            fragment_shader.map(|descriptor| device.create_shader_module(descriptor));
 
-        let pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("wgpu-text Render Pipeline Layout"),
-                bind_group_layouts: &[&cache.bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("wgpu-text Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::buffer_layout()],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint16),
-                ..Default::default()
+        let vertex_buffer =
+            create_vertex_buffer(device, vertex_capacity, config.vertex_usage);
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-text Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &cache.bind_group_layout,
+                &effects.bind_group_layout,
+                &mask.bind_group_layout,
+            ],
+            push_constant_ranges: match config.push_constants {
+                true => &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::VERTEX,
+                    range: 0..std::mem::size_of::<Matrix>() as u32,
+                }],
+                false => &[],
             },
-            depth_stencil,
-            multisample,
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: render_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview,
+        });
+
+        let pipeline = create_render_pipeline(
+            device,
+            &layout,
+            &shader,
+            fragment_shader.as_ref(),
+            &config,
+            false,
+        );
+        let subpixel = config.subpixel.then(|| {
+            create_subpixel_pipelines(device, &layout, &shader, &config, false).map(
+                |plain| Variants {
+                    plain,
+                    styled: None,
+                },
+            )
         });
 
         Self {
-            inner: pipeline,
+            pipelines: vec![(
+                config.blend_mode,
+                Variants {
+                    plain: pipeline,
+                    styled: None,
+                },
+            )],
+            current: 0,
+            subpixel,
+            styled: false,
+            shader,
+            fragment_shader,
+            layout,
+            config,
+
             cache,
+            effects,
+            mask,
 
             vertex_buffer,
-            vertex_buffer_len: 0,
+            vertex_buffer_len: vertex_capacity,
             vertices: 0,
+            style_buffer: None,
+            style_buffer_len: 0,
+            styles: false,
+        }
+    }
+
+    #[inline]
+    pub fn blend_mode(&self) -> BlendMode {
+        self.config.blend_mode
+    }
+
+    /// Switches to the render pipeline of the given blend mode, creating it if it
+    /// wasn't used before.
+    pub fn set_blend_mode(&mut self, device: &wgpu::Device, blend_mode: BlendMode) {
+        self.config.blend_mode = blend_mode;
+        self.current = match self.pipelines.iter().position(|(m, _)| *m == blend_mode) {
+            Some(index) => index,
+            None => {
+                let create = |styled| {
+                    create_render_pipeline(
+                        device,
+                        &self.layout,
+                        &self.shader,
+                        self.fragment_shader.as_ref(),
+                        &self.config,
+                        styled,
+                    )
+                };
+                let variants = Variants {
+                    plain: create(false),
+                    styled: self.styled.then(|| create(true)),
+                };
+                self.pipelines.push((blend_mode, variants));
+                self.pipelines.len() - 1
+            }
+        };
+    }
+
+    /// Creates the pipeline variants reading vertex styles, if not created yet.
+    fn create_styled_pipelines(&mut self, device: &wgpu::Device) {
+        if self.styled {
+            return;
+        }
+        self.styled = true;
+
+        let mut config = self.config.clone();
+        for (blend_mode, variants) in &mut self.pipelines {
+            config.blend_mode = *blend_mode;
+            variants.styled = Some(create_render_pipeline(
+                device,
+                &self.layout,
+                &self.shader,
+                self.fragment_shader.as_ref(),
+                &config,
+                true,
+            ));
+        }
+        if let Some(subpixel) = &mut self.subpixel {
+            let styled = create_subpixel_pipelines(
+                device,
+                &self.layout,
+                &self.shader,
+                &self.config,
+                true,
+            );
+            for (variants, pipeline) in subpixel.iter_mut().zip(styled) {
+                variants.styled = Some(pipeline);
+            }
         }
     }
 
-    /// Raw draw.
+    /// Raw draw. Subpixel drawing uses two draws, masking the render target with the
+    /// per channel coverage first and adding the text color after.
+    #[inline]
     pub fn draw<'pass>(&'pass self, rpass: &mut wgpu::RenderPass<'pass>) {
-        if self.vertices != 0 {
-            rpass.set_pipeline(&self.inner);
-            rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        self.draw_buffer(
+            rpass,
+            &self.vertex_buffer,
+            self.style_buffer(),
+            self.vertices,
+        );
+    }
+
+    /// Raw draw of `vertices` from another vertex `buffer`, and their styles from the
+    /// `styles` buffer if they have any, see [`Pipeline::draw`].
+    pub fn draw_buffer<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        buffer: &'pass wgpu::Buffer,
+        styles: Option<&'pass wgpu::Buffer>,
+        vertices: u32,
+    ) {
+        let buffers = (buffer, styles);
+        match &self.subpixel {
+            Some(pipelines) => {
+                for variants in pipelines {
+                    let pipeline = variants.get(styles.is_some());
+                    self.draw_with(rpass, pipeline, EffectSlot::Plain, buffers, vertices);
+                }
+            }
+            None => {
+                let pipeline = self.pipelines[self.current].1.get(styles.is_some());
+                self.draw_with(rpass, pipeline, EffectSlot::Plain, buffers, vertices);
+            }
+        }
+    }
+
+    /// Raw draw using the effect parameters stored in the provided `slot`.
+    #[inline]
+    pub fn draw_effect<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        slot: EffectSlot,
+    ) {
+        let styles = self.style_buffer();
+        let pipeline = self.pipelines[self.current].1.get(styles.is_some());
+        let buffers = (&self.vertex_buffer, styles);
+        self.draw_with(rpass, pipeline, slot, buffers, self.vertices);
+    }
+
+    fn draw_with<'pass>(
+        &'pass self,
+        rpass: &mut wgpu::RenderPass<'pass>,
+        pipeline: &'pass wgpu::RenderPipeline,
+        slot: EffectSlot,
+        (buffer, styles): (&'pass wgpu::Buffer, Option<&'pass wgpu::Buffer>),
+        vertices: u32,
+    ) {
+        if vertices != 0 {
+            rpass.set_pipeline(pipeline);
+            rpass.set_vertex_buffer(0, buffer.slice(..));
+            if let Some(styles) = styles {
+                rpass.set_vertex_buffer(1, styles.slice(..));
+            }
             rpass.set_bind_group(0, &self.cache.bind_group, &[]);
+            rpass.set_bind_group(
+                1,
+                &self.effects.bind_group,
+                &[self.effects.offset(slot)],
+            );
+            rpass.set_bind_group(2, self.mask.bind_group(slot), &[]);
+            if self.config.push_constants {
+                rpass.set_push_constants(
+                    wgpu::ShaderStages::VERTEX,
+                    0,
+                    bytemuck::cast_slice(self.cache.matrix()),
+                );
+            }
 
-            rpass.draw(0..4, 0..self.vertices);
+            rpass.draw(0..4, 0..vertices);
         }
     }
-    // TODO look into preallocating the vertex buffer instead of constantly reallocating
+    /// Writes the `changed` range of the `vertices` into the vertex buffer and of their
+    /// `styles`, either empty or one per vertex, into the style buffer. Returns the
+    /// range written, all vertices if a buffer had to be reallocated. Buffers only get
+    /// reallocated when the data doesn't fit, growing to 1.5 times its length, and
+    /// never shrink.
     pub fn update_vertex_buffer(
         &mut self,
-        vertices: Vec<Vertex>,
+        (vertices, styles): (&[Vertex], &[VertexStyle]),
+        changed: Range<usize>,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-    ) {
-        self.vertices = vertices.len() as u32;
-        let data: &[u8] = bytemuck::cast_slice(&vertices);
+    ) -> Range<usize> {
+        let changed = self.reserve_vertices(device, (vertices, styles), changed);
+        let mut write = |buffer: &wgpu::Buffer, offset, data: &[u8]| {
+            if !data.is_empty() {
+                queue.write_buffer(buffer, offset, data);
+            }
+        };
+        self.write_vertices((vertices, styles), changed.clone(), &mut write);
+        changed
+    }
 
-        if vertices.len() > self.vertex_buffer_len {
-            self.vertex_buffer_len = vertices.len();
+    /// Like [`Pipeline::update_vertex_buffer`], but copies the `vertices` and `styles`
+    /// through the staging `belt`, recording the copy into the `encoder`.
+    pub fn update_vertex_buffer_staged(
+        &mut self,
+        (vertices, styles): (&[Vertex], &[VertexStyle]),
+        changed: Range<usize>,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        belt: &mut wgpu::util::StagingBelt,
+    ) -> Range<usize> {
+        let changed = self.reserve_vertices(device, (vertices, styles), changed);
+        let mut write = |buffer: &wgpu::Buffer, offset, data: &[u8]| {
+            if let Some(size) = NonZeroU64::new(data.len() as u64) {
+                belt.write_buffer(encoder, buffer, offset, size, device)
+                    .copy_from_slice(data);
+            }
+        };
+        self.write_vertices((vertices, styles), changed.clone(), &mut write);
+        changed
+    }
 
-            self.vertex_buffer =
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("wgpu-text Vertex Buffer"),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                    contents: data,
-                });
+    /// Passes the `changed` range of the `vertices` and `styles` to `write` along with
+    /// their buffer and byte offset.
+    fn write_vertices(
+        &self,
+        (vertices, styles): (&[Vertex], &[VertexStyle]),
+        changed: Range<usize>,
+        write: &mut impl FnMut(&wgpu::Buffer, wgpu::BufferAddress, &[u8]),
+    ) {
+        let offset = |size: usize| (changed.start * size) as wgpu::BufferAddress;
+        write(
+            &self.vertex_buffer,
+            offset(std::mem::size_of::<Vertex>()),
+            bytemuck::cast_slice(&vertices[changed.clone()]),
+        );
+        if let Some(buffer) = self.style_buffer() {
+            write(
+                buffer,
+                offset(std::mem::size_of::<VertexStyle>()),
+                bytemuck::cast_slice(&styles[changed.clone()]),
+            );
+        }
+    }
 
-            return;
+    /// Sets the vertex count, growing the vertex buffer if it can't hold the `vertices`
+    /// and the style buffer if it can't hold the `styles`. Returns the range of
+    /// vertices to write, all of them if a buffer was reallocated.
+    fn reserve_vertices(
+        &mut self,
+        device: &wgpu::Device,
+        (vertices, styles): (&[Vertex], &[VertexStyle]),
+        changed: Range<usize>,
+    ) -> Range<usize> {
+        let (len, styles) = (vertices.len(), styles.len());
+        debug_assert!(styles == 0 || styles == len);
+        self.vertices = len as u32;
+        self.styles = styles != 0;
+
+        let mut changed = changed;
+        if len > self.vertex_buffer_len {
+            self.vertex_buffer_len = len + len / 2;
+            self.vertex_buffer = create_vertex_buffer(
+                device,
+                self.vertex_buffer_len,
+                self.config.vertex_usage,
+            );
+            changed = 0..len;
+        }
+        if styles > self.style_buffer_len {
+            self.style_buffer_len = styles + styles / 2;
+            self.style_buffer = Some(create_style_buffer(device, self.style_buffer_len));
+            changed = 0..len;
         }
-        queue.write_buffer(&self.vertex_buffer, 0, data);
+        if self.styles {
+            self.create_styled_pipelines(device);
+        }
+        changed
+    }
+
+    /// Creates a buffer holding the vertex `styles` for drawing static vertices,
+    /// preparing the pipelines drawing them.
+    pub fn create_style_buffer(
+        &mut self,
+        device: &wgpu::Device,
+        styles: &[VertexStyle],
+    ) -> wgpu::Buffer {
+        use wgpu::util::DeviceExt;
+
+        self.create_styled_pipelines(device);
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-text Static Style Buffer"),
+            contents: bytemuck::cast_slice(styles),
+            usage: wgpu::BufferUsages::VERTEX,
+        })
+    }
+
+    /// Returns the style buffer if the uploaded vertices have styles.
+    #[inline]
+    pub fn style_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.style_buffer.as_ref().filter(|_| self.styles)
+    }
+
+    /// Returns the number of vertices the vertex buffer can hold.
+    #[inline]
+    pub fn vertex_capacity(&self) -> usize {
+        self.vertex_buffer_len
+    }
+
+    /// Returns the vertex buffer, replaced whenever it grows.
+    #[inline]
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.vertex_buffer
     }
 
+    /// Writes the `matrix` into the uniform buffer, or only keeps it for the next
+    /// draws when it's pushed as a push constant.
     #[inline]
     pub fn update_matrix(&mut self, matrix: Matrix, queue: &wgpu::Queue) {
-        self.cache.update_matrix(matrix, queue);
+        match self.config.push_constants {
+            true => self.cache.set_matrix(matrix),
+            false => self.cache.update_matrix(matrix, queue),
+        }
+    }
+
+    #[inline]
+    pub fn set_sampler_filters(
+        &mut self,
+        device: &wgpu::Device,
+        filters: SamplerFilters,
+    ) {
+        self.cache.set_filters(device, filters);
+    }
+
+    #[inline]
+    pub fn sampler_filters(&self) -> SamplerFilters {
+        self.cache.filters()
+    }
+
+    #[inline]
+    pub fn update_effect(
+        &mut self,
+        slot: EffectSlot,
+        effect: EffectUniform,
+        queue: &wgpu::Queue,
+    ) {
+        self.effects.update(slot, effect, queue);
+    }
+
+    /// Keeps the other parameters of `slot`, see [`Effects::update_bounds`].
+    #[inline]
+    pub fn update_effect_bounds(
+        &mut self,
+        slot: EffectSlot,
+        bounds: [f32; 4],
+        queue: &wgpu::Queue,
+    ) {
+        self.effects.update_bounds(slot, bounds, queue);
+    }
+
+    /// Sets the texture used by masked or image filled drawing, depending on `slot`.
+    #[inline]
+    pub fn set_mask(
+        &mut self,
+        device: &wgpu::Device,
+        slot: EffectSlot,
+        view: &wgpu::TextureView,
+    ) {
+        self.mask.set(device, slot, view);
+    }
+
+    #[inline]
+    pub fn set_dilation(&mut self, radius: f32, queue: &wgpu::Queue) {
+        self.effects.set_dilation(radius, queue);
+    }
+
+    #[inline]
+    pub fn set_alpha(&mut self, alpha: f32, queue: &wgpu::Queue) {
+        self.effects.set_alpha(alpha, queue);
+    }
+
+    #[inline]
+    pub fn set_emboss(
+        &mut self,
+        strength: f32,
+        light_dir: [f32; 2],
+        queue: &wgpu::Queue,
+    ) {
+        self.effects.set_emboss(strength, light_dir, queue);
+    }
+
+    #[inline]
+    pub fn dilation(&self) -> f32 {
+        self.effects.dilation()
+    }
+
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        self.effects.alpha()
     }
 
     #[inline]
@@ -135,103 +620,522 @@ impl Pipeline {
         self.cache.update_texture(size, data, queue);
     }
 
+    #[inline]
+    pub fn update_color_texture(
+        &mut self,
+        size: Rectangle<u32>,
+        data: &[u8],
+        queue: &wgpu::Queue,
+    ) {
+        self.cache.update_color_texture(size, data, queue);
+    }
+
+    #[inline]
+    pub fn cache_texture(&self) -> &wgpu::Texture {
+        self.cache.texture()
+    }
+
+    #[inline]
+    pub fn copy_cache(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> TextureReadback {
+        self.cache.copy_texture(device, encoder)
+    }
+
     #[inline]
     pub fn resize_texture(&mut self, device: &wgpu::Device, tex_dimensions: (u32, u32)) {
         self.cache.recreate_texture(device, tex_dimensions);
     }
 }
 
+/// The shader without its matrix, declared by one of the two matrix sources.
+const SHADER: &str = include_str!("shader/shader.wgsl");
+const UNIFORM_MATRIX: &str = include_str!("shader/matrix_uniform.wgsl");
+const PUSH_CONSTANT_MATRIX: &str = include_str!("shader/matrix_push_constant.wgsl");
+
+fn create_vertex_buffer(
+    device: &wgpu::Device,
+    capacity: usize,
+    usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-text Vertex Buffer"),
+        size: (capacity * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | usage,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_style_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-text Style Buffer"),
+        size: (capacity * std::mem::size_of::<VertexStyle>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[inline]
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_shader: Option<&wgpu::ShaderModule>,
+    config: &PipelineConfig,
+    styled: bool,
+) -> wgpu::RenderPipeline {
+    let blend = config.blend_state();
+    let fragment_shader = fragment_shader.unwrap_or(shader);
+    create_pipeline(
+        device,
+        layout,
+        (shader, styled),
+        fragment_shader,
+        config,
+        "fs_main",
+        blend,
+    )
+}
+
+/// Creates the coverage masking and color adding pipelines of subpixel drawing.
+fn create_subpixel_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    config: &PipelineConfig,
+    styled: bool,
+) -> [wgpu::RenderPipeline; 2] {
+    let (mask, add) = subpixel_blend_states();
+    [("fs_subpixel_mask", mask), ("fs_subpixel_color", add)].map(|(entry, blend)| {
+        create_pipeline(
+            device,
+            layout,
+            (shader, styled),
+            shader,
+            config,
+            entry,
+            blend,
+        )
+    })
+}
+
+/// Returns the blend states of the subpixel pipelines. The first one multiplies the
+/// render target by one minus the per channel coverage, the second one adds the text
+/// color multiplied by the per channel coverage.
+fn subpixel_blend_states() -> (wgpu::BlendState, wgpu::BlendState) {
+    let mask = wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::OneMinusSrc,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+    };
+    let add = wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendState::ALPHA_BLENDING.alpha,
+    };
+    (mask, add)
+}
+
+/// Creates a render pipeline, its vertex stage also reading vertex styles from a
+/// second vertex buffer if `styled`.
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    (shader, styled): (&wgpu::ShaderModule, bool),
+    fragment_shader: &wgpu::ShaderModule,
+    config: &PipelineConfig,
+    fragment_entry_point: &str,
+    blend: wgpu::BlendState,
+) -> wgpu::RenderPipeline {
+    let (entry_point, buffers): (_, &[_]) = match styled {
+        true => (
+            "vs_styled",
+            &[Vertex::buffer_layout(), VertexStyle::buffer_layout()],
+        ),
+        false => ("vs_main", &[Vertex::buffer_layout()]),
+    };
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("wgpu-text Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point,
+            buffers,
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            strip_index_format: Some(wgpu::IndexFormat::Uint16),
+            ..Default::default()
+        },
+        depth_stencil: config.depth_stencil.clone(),
+        multisample: config.multisample,
+        fragment: Some(wgpu::FragmentState {
+            module: fragment_shader,
+            entry_point: fragment_entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: config.render_format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: config.multiview,
+    })
+}
+
+/// Instance of a glyph or solid quad, see [`TextBrush::vertices()`].
+///
+/// Vertices are stepped per instance ([`wgpu::VertexStepMode::Instance`]), every
+/// instance is drawn as a triangle strip of 4 vertices, so `n` instances are drawn with
+/// `rpass.draw(0..4, 0..n)`. [`Self::buffer_layout()`] maps the fields in order to
+/// shader locations `0` to `4`, all `f32` based, without padding.
+///
+/// Rotated, gradient and transformed glyphs additionally need a [`VertexStyle`] per
+/// instance, in a second vertex buffer.
+///
+/// Pixel coordinates are the glyph layout coordinates, before the view matrix.
+///
+/// [`TextBrush::vertices()`]: crate::TextBrush::vertices
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
-    top_left: [f32; 3],
-    bottom_right: [f32; 2],
-    tex_top_left: [f32; 2],
-    tex_bottom_right: [f32; 2],
-    color: [f32; 4],
+    /// Top left corner in pixels and the depth (*z*).
+    pub top_left: [f32; 3],
+    /// Bottom right corner in pixels.
+    pub bottom_right: [f32; 2],
+    /// Normalized cache texture coordinates of the corners. Negative for solid quads,
+    /// offset by `2.0` for color glyphs (see
+    /// [`BrushBuilder::with_color_emoji()`](crate::BrushBuilder::with_color_emoji)).
+    pub tex_top_left: [f32; 2],
+    pub tex_bottom_right: [f32; 2],
+    /// RGBA color, at the left or top edge for gradients.
+    pub color: [f32; 4],
 }
 
 impl Vertex {
-    pub fn to_vertex(
+    /// Returns the instance stepped layout of a vertex buffer holding [`Vertex`]
+    /// instances, for custom pipelines drawing them.
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                },
+            ],
+        }
+    }
+}
+
+/// Optional styling of a [`Vertex`] instance, see [`TextBrush::vertex_styles()`].
+///
+/// Only uploaded (into a second, instance stepped vertex buffer) and drawn with
+/// pipelines reading it while some queued glyph is rotated, has a gradient or a
+/// transform, so text without these options keeps the smaller instances.
+/// [`Self::buffer_layout()`] maps the fields in order to shader locations `5` to `9`.
+///
+/// [`TextBrush::vertex_styles()`]: crate::TextBrush::vertex_styles
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexStyle {
+    /// Rotation around the quad center in radians.
+    pub rotation: f32,
+    /// Gradient end color, at the right or bottom edge.
+    pub color_end: [f32; 4],
+    /// Gradient direction, 0.0 horizontal and 1.0 vertical.
+    pub vertical: f32,
+    /// Section transform applied after the rotation, a column-major 2x2 matrix and a
+    /// translation in pixels.
+    pub transform: [f32; 4],
+    pub translation: [f32; 2],
+}
+
+impl VertexStyle {
+    /// Style without any effect on an instance of `color`.
+    #[inline]
+    pub(crate) fn plain(color: [f32; 4]) -> Self {
+        Self {
+            rotation: 0.0,
+            color_end: color,
+            vertical: 0.0,
+            transform: [1.0, 0.0, 0.0, 1.0],
+            translation: [0.0; 2],
+        }
+    }
+
+    /// Returns the instance stepped layout of a vertex buffer holding [`VertexStyle`]
+    /// instances, for custom pipelines drawing them.
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    offset: 0,
+                    shader_location: 5,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<f32>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                },
+            ],
+        }
+    }
+}
+
+/// [`Vertex`] with its [`VertexStyle`], as kept by the brush until uploading.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Instance {
+    pub vertex: Vertex,
+    pub style: VertexStyle,
+}
+
+impl Instance {
+    pub(crate) fn to_vertex(
         glyph_brush::GlyphVertex {
             mut tex_coords,
             pixel_coords,
             bounds,
             extra,
-        }: glyph_brush::GlyphVertex,
-    ) -> Vertex {
-        let bounds = bounds;
+        }: glyph_brush::GlyphVertex<GlyphExtra>,
+    ) -> Instance {
         let mut rect = Rect {
             min: point(pixel_coords.min.x, pixel_coords.min.y),
             max: point(pixel_coords.max.x, pixel_coords.max.y),
         };
 
-        // handle overlapping bounds, modify uv_rect to preserve texture aspect
+        // Crop glyphs overlapping the bounds, cropping their texture coordinates by the
+        // same fraction, so the visible part keeps its 1:1 mapping to the cache texture.
+        // Degenerate (zero sized) glyphs keep their texture coordinates.
         if rect.max.x > bounds.max.x {
             let old_width = rect.width();
             rect.max.x = bounds.max.x;
-            tex_coords.max.x =
-                tex_coords.min.x + tex_coords.width() * rect.width() / old_width;
+            if old_width > 0.0 {
+                tex_coords.max.x =
+                    tex_coords.min.x + tex_coords.width() * rect.width() / old_width;
+            }
         }
         if rect.min.x < bounds.min.x {
             let old_width = rect.width();
             rect.min.x = bounds.min.x;
-            tex_coords.min.x =
-                tex_coords.max.x - tex_coords.width() * rect.width() / old_width;
+            if old_width > 0.0 {
+                tex_coords.min.x =
+                    tex_coords.max.x - tex_coords.width() * rect.width() / old_width;
+            }
         }
         if rect.max.y > bounds.max.y {
             let old_height = rect.height();
             rect.max.y = bounds.max.y;
-            tex_coords.max.y =
-                tex_coords.min.y + tex_coords.height() * rect.height() / old_height;
+            if old_height > 0.0 {
+                tex_coords.max.y =
+                    tex_coords.min.y + tex_coords.height() * rect.height() / old_height;
+            }
         }
         if rect.min.y < bounds.min.y {
             let old_height = rect.height();
             rect.min.y = bounds.min.y;
-            tex_coords.min.y =
-                tex_coords.max.y - tex_coords.height() * rect.height() / old_height;
+            if old_height > 0.0 {
+                tex_coords.min.y =
+                    tex_coords.max.y - tex_coords.height() * rect.height() / old_height;
+            }
         }
 
+        Instance {
+            vertex: Vertex {
+                top_left: [rect.min.x, rect.min.y, extra.z],
+                bottom_right: [rect.max.x, rect.max.y],
+                tex_top_left: [tex_coords.min.x, tex_coords.min.y],
+                tex_bottom_right: [tex_coords.max.x, tex_coords.max.y],
+                color: extra.color,
+            },
+            style: VertexStyle {
+                rotation: extra.rotation,
+                color_end: extra.color_end,
+                vertical: (extra.gradient == GradientDirection::Vertical) as u8 as f32,
+                transform: extra.transform,
+                translation: extra.translation,
+            },
+        }
+    }
+
+    /// Creates an instance of a solid color quad, marked by negative texture
+    /// coordinates.
+    pub(crate) fn solid(rect: Rect, z: f32, color: [f32; 4]) -> Instance {
         Vertex {
-            top_left: [rect.min.x, rect.min.y, extra.z],
+            top_left: [rect.min.x, rect.min.y, z],
             bottom_right: [rect.max.x, rect.max.y],
-            tex_top_left: [tex_coords.min.x, tex_coords.min.y],
-            tex_bottom_right: [tex_coords.max.x, tex_coords.max.y],
-            color: extra.color,
+            tex_top_left: [-1.0, -1.0],
+            tex_bottom_right: [-1.0, -1.0],
+            color,
         }
+        .into()
     }
 
-    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x2,
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x2,
-                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
-                    shader_location: 2,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x2,
-                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
-                    shader_location: 3,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
-                    shader_location: 4,
-                },
-            ],
+    /// Returns `false` for quads without any area, like glyphs cropped away entirely by
+    /// their section bounds.
+    #[inline]
+    pub(crate) fn is_visible(&self) -> bool {
+        let Vertex {
+            top_left,
+            bottom_right,
+            ..
+        } = self.vertex;
+        bottom_right[0] > top_left[0] && bottom_right[1] > top_left[1]
+    }
+
+    /// Returns `true` if the style changes how the vertex is drawn.
+    #[inline]
+    pub(crate) fn is_styled(&self) -> bool {
+        self.style != VertexStyle::plain(self.vertex.color)
+    }
+
+    /// Returns the horizontal range covered by the `instances` quads, if any.
+    pub(crate) fn x_range(instances: &[Instance]) -> Option<(f32, f32)> {
+        instances.iter().fold(None, |range, i| {
+            let (left, right) = (i.vertex.top_left[0], i.vertex.bottom_right[0]);
+            let (min, max) = range.unwrap_or((left, right));
+            Some((min.min(left), max.max(right)))
+        })
+    }
+
+    /// Moves the instance by `offset` pixels on the GPU, after its transform.
+    pub(crate) fn translate(&mut self, offset: (f32, f32)) {
+        self.style.translation[0] += offset.0;
+        self.style.translation[1] += offset.1;
+    }
+}
+
+impl From<Vertex> for Instance {
+    #[inline]
+    fn from(vertex: Vertex) -> Self {
+        Self {
+            vertex,
+            style: VertexStyle::plain(vertex.color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect((min_x, min_y): (f32, f32), (max_x, max_y): (f32, f32)) -> Rect {
+        Rect {
+            min: point(min_x, min_y),
+            max: point(max_x, max_y),
+        }
+    }
+
+    fn unbounded() -> Rect {
+        rect(
+            (f32::NEG_INFINITY, f32::NEG_INFINITY),
+            (f32::INFINITY, f32::INFINITY),
+        )
+    }
+
+    fn instance(pixel_coords: Rect, bounds: Rect, extra: &GlyphExtra) -> Instance {
+        Instance::to_vertex(glyph_brush::GlyphVertex {
+            tex_coords: rect((0.0, 0.0), (0.5, 0.25)),
+            pixel_coords,
+            bounds,
+            extra,
+        })
+    }
+
+    #[test]
+    fn clipped_glyph_keeps_texture_scale() {
+        let extra = GlyphExtra::from(glyph_brush::Extra::default());
+        let pixel_coords = rect((0.0, 0.0), (20.0, 10.0));
+        let full = instance(pixel_coords, unbounded(), &extra).vertex;
+        let bounds = rect((-5.0, 4.0), (10.0, 100.0));
+
+        let clipped = instance(pixel_coords, bounds, &extra).vertex;
+
+        assert_eq!(clipped.top_left[..2], [0.0, 4.0]);
+        assert_eq!(clipped.bottom_right, [10.0, 10.0]);
+        // Texels per pixel are unchanged, the visible part is cropped, not squeezed.
+        let per_px = |v: Vertex, axis: usize| {
+            (v.tex_bottom_right[axis] - v.tex_top_left[axis])
+                / (v.bottom_right[axis] - v.top_left[axis])
+        };
+        for axis in 0..2 {
+            assert!((per_px(clipped, axis) - per_px(full, axis)).abs() < 1e-6);
+        }
+        // The left and bottom parts of the glyph stay.
+        assert_eq!(clipped.tex_top_left[0], full.tex_top_left[0]);
+        assert_eq!(clipped.tex_bottom_right[1], full.tex_bottom_right[1]);
+    }
+
+    #[test]
+    fn clipped_degenerate_glyphs_stay_finite() {
+        let extra = GlyphExtra::from(glyph_brush::Extra::default());
+        let bounds = rect((0.0, 6.0), (4.0, 8.0));
+
+        for pixel_coords in [rect((5.0, 0.0), (5.0, 10.0)), rect((0.0, 5.0), (10.0, 5.0))]
+        {
+            let glyph = instance(pixel_coords, bounds, &extra);
+            let Vertex {
+                tex_top_left,
+                tex_bottom_right,
+                ..
+            } = glyph.vertex;
+
+            assert!(tex_top_left
+                .iter()
+                .chain(&tex_bottom_right)
+                .all(|c| c.is_finite()));
+            assert!(!glyph.is_visible());
         }
     }
 }
@@ -0,0 +1,666 @@
+use std::{
+    borrow::Cow,
+    hash::{Hash, Hasher},
+};
+
+use glyph_brush::{
+    ab_glyph::{point, Rect},
+    Color, Extra, OwnedSection, Section, Text,
+};
+
+/// Wrapper over [`glyph_brush::Section`] carrying additional **wgpu-text** specific
+/// options which are applied by [`TextBrush`](crate::TextBrush) when queuing or
+/// measuring the section.
+///
+/// Usually created through the [`SectionExt`] methods.
+#[derive(Debug, Clone)]
+pub struct StyledSection<'a> {
+    pub section: Cow<'a, Section<'a>>,
+    pub(crate) placeholder: Option<(&'a str, Color)>,
+    pub(crate) order: i32,
+    pub(crate) reveal: Option<f32>,
+    pub(crate) vertical_run_align: VerticalRunAlign,
+    pub(crate) line_backgrounds: &'a [Color],
+    pub(crate) underline: Option<Color>,
+    pub(crate) strikethrough: Option<Color>,
+    pub(crate) jitter: Option<Jitter>,
+    pub(crate) layer: Option<u32>,
+    pub(crate) edge_fades: [Option<(FadeEdge, f32)>; 4],
+    pub(crate) tabular_figures: bool,
+    pub(crate) letter_spacing: f32,
+    pub(crate) tab_width: f32,
+    pub(crate) line_height: f32,
+    pub(crate) layout_mode: LayoutMode,
+    pub(crate) shadow: Option<((f32, f32), Color)>,
+    pub(crate) gradient: Option<(Color, GradientDirection)>,
+    pub(crate) transform: Option<Transform2D>,
+    pub(crate) local_origin: bool,
+    pub(crate) wrap_width: Option<f32>,
+    pub(crate) clip_rect: Option<Rect>,
+}
+
+impl<'a> StyledSection<'a> {
+    /// Returns the section which should be laid out with all options applied.
+    pub(crate) fn resolve(self) -> Cow<'a, Section<'a>> {
+        let mut section = self.section;
+
+        if let Some((text, color)) = self.placeholder {
+            if section.text.iter().all(|t| t.text.is_empty()) {
+                let base = section
+                    .text
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| Text::new(text));
+                section.to_mut().text = vec![Text {
+                    text,
+                    extra: glyph_brush::Extra {
+                        color,
+                        z: base.extra.z,
+                    },
+                    ..base
+                }];
+            }
+        }
+
+        if let Some(width) = self.wrap_width {
+            section.to_mut().bounds.0 = width;
+        }
+
+        section
+    }
+
+    /// Returns the rectangle glyphs are clipped to instead of the section bounds, if
+    /// the clipping is separated from them by [`SectionExt::wrap_width()`] or
+    /// [`SectionExt::clip_rect()`].
+    pub(crate) fn clip(&self) -> Option<Rect> {
+        let unbounded = Rect {
+            min: point(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: point(f32::INFINITY, f32::INFINITY),
+        };
+        self.clip_rect
+            .or_else(|| self.wrap_width.map(|_| unbounded))
+    }
+}
+
+impl<'a> From<Cow<'a, Section<'a>>> for StyledSection<'a> {
+    fn from(section: Cow<'a, Section<'a>>) -> Self {
+        Self {
+            section,
+            placeholder: None,
+            order: 0,
+            reveal: None,
+            vertical_run_align: VerticalRunAlign::Baseline,
+            line_backgrounds: &[],
+            underline: None,
+            strikethrough: None,
+            jitter: None,
+            layer: None,
+            edge_fades: [None; 4],
+            tabular_figures: false,
+            letter_spacing: 0.0,
+            tab_width: 0.0,
+            line_height: 1.0,
+            layout_mode: LayoutMode::Horizontal,
+            shadow: None,
+            gradient: None,
+            transform: None,
+            local_origin: false,
+            wrap_width: None,
+            clip_rect: None,
+        }
+    }
+}
+
+impl<'a> From<Section<'a>> for StyledSection<'a> {
+    #[inline]
+    fn from(section: Section<'a>) -> Self {
+        Self::from(Cow::Owned(section))
+    }
+}
+
+impl<'a> From<&'a Section<'a>> for StyledSection<'a> {
+    #[inline]
+    fn from(section: &'a Section<'a>) -> Self {
+        Self::from(Cow::Borrowed(section))
+    }
+}
+
+impl<'a> From<&'a OwnedSection> for StyledSection<'a> {
+    #[inline]
+    fn from(section: &'a OwnedSection) -> Self {
+        Self::from(Cow::Owned(section.to_borrowed()))
+    }
+}
+
+/// Vertical alignment of differently scaled text runs sharing a line, see
+/// [`SectionExt::vertical_run_align()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalRunAlign {
+    /// All runs share the line baseline.
+    #[default]
+    Baseline,
+    /// Tops of capital letters are aligned with the largest run of the line.
+    CapHeight,
+    /// Vertical centers (between ascent and descent) are aligned with the largest run
+    /// of the line.
+    Center,
+}
+
+/// Direction in which glyphs of a section advance, see [`SectionExt::layout_mode()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutMode {
+    /// Glyphs advance left to right along lines stacked top to bottom.
+    #[default]
+    Horizontal,
+    /// Glyphs advance top to bottom along columns stacked right to left, like
+    /// vertical CJK writing.
+    Vertical,
+}
+
+/// Edge towards which glyphs fade out, see [`SectionExt::edge_fade()`]. Edge
+/// coordinates are in the same coordinates as section positions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FadeEdge {
+    /// Horizontal line at the given *y*, glyphs above it are hidden.
+    Top(f32),
+    /// Horizontal line at the given *y*, glyphs below it are hidden.
+    Bottom(f32),
+    /// Vertical line at the given *x*, glyphs left of it are hidden.
+    Left(f32),
+    /// Vertical line at the given *x*, glyphs right of it are hidden.
+    Right(f32),
+}
+
+impl FadeEdge {
+    /// Returns the opacity of a glyph centered at `center` fading out over `falloff`
+    /// pixels towards the edge.
+    pub(crate) fn opacity(self, center: (f32, f32), falloff: f32) -> f32 {
+        let distance = match self {
+            FadeEdge::Top(y) => center.1 - y,
+            FadeEdge::Bottom(y) => y - center.1,
+            FadeEdge::Left(x) => center.0 - x,
+            FadeEdge::Right(x) => x - center.0,
+        };
+        match falloff > 0.0 {
+            true => (distance / falloff).clamp(0.0, 1.0),
+            false => (distance >= 0.0) as u8 as f32,
+        }
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        match self {
+            FadeEdge::Top(_) => 0,
+            FadeEdge::Bottom(_) => 1,
+            FadeEdge::Left(_) => 2,
+            FadeEdge::Right(_) => 3,
+        }
+    }
+}
+
+/// Direction of a section color gradient, see [`SectionExt::gradient()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GradientDirection {
+    /// From the left to the right section edge.
+    #[default]
+    Horizontal,
+    /// From the top to the bottom section edge.
+    Vertical,
+}
+
+impl GradientDirection {
+    /// Returns the range covered by the `rect` along the gradient direction.
+    #[inline]
+    pub(crate) fn range(self, rect: Rect) -> (f32, f32) {
+        match self {
+            GradientDirection::Horizontal => (rect.min.x, rect.max.x),
+            GradientDirection::Vertical => (rect.min.y, rect.max.y),
+        }
+    }
+}
+
+/// Adds **wgpu-text** specific options to sections, turning them into a
+/// [`StyledSection`].
+///
+/// ```rust
+/// use wgpu_text::{glyph_brush::Section, SectionExt};
+///
+/// // Queue it like any other section.
+/// let section = Section::default().placeholder("Type here...", [0.5, 0.5, 0.5, 1.0]);
+/// ```
+pub trait SectionExt<'a>: Into<StyledSection<'a>> {
+    /// Converts into a [`StyledSection`] without any options set.
+    #[inline]
+    fn styled(self) -> StyledSection<'a> {
+        self.into()
+    }
+
+    /// Shows `text` in the provided `color` (typically dimmed) instead of the section
+    /// text when all of the section text is empty, like the placeholder of an input
+    /// field.
+    ///
+    /// The placeholder uses the scale, font and *z* of the first section text (or the
+    /// [`glyph_brush::Text`] defaults if there is none). Measuring the section measures
+    /// whichever text ends up being shown.
+    fn placeholder(self, text: &'a str, color: Color) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.placeholder = Some((text, color));
+        styled
+    }
+
+    /// Sets the render order key of the section.
+    ///
+    /// Queued sections are drawn in increasing `order` (higher on top), regardless of
+    /// their position in the queue. Sections with equal keys keep their queue order.
+    ///
+    /// Defaults to `0`.
+    fn order(self, order: i32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.order = order;
+        styled
+    }
+
+    /// Reveals only the leading `progress` (`0.0..=1.0`) part of the section glyphs,
+    /// like text being typed out in dialogue or cutscenes.
+    ///
+    /// The glyph at the reveal edge fades in instead of popping up. Layout and
+    /// measurement still use the full text, so nothing shifts while revealing.
+    fn reveal(self, progress: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.reveal = Some(progress.clamp(0.0, 1.0));
+        styled
+    }
+
+    /// Sets how runs of different scales sharing a line are aligned vertically, e.g.
+    /// centering normal text next to a large decorative initial.
+    ///
+    /// Glyphs are shifted after layout, so line heights and measurement still use the
+    /// baseline aligned layout.
+    ///
+    /// Defaults to [`VerticalRunAlign::Baseline`].
+    fn vertical_run_align(self, align: VerticalRunAlign) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.vertical_run_align = align;
+        styled
+    }
+
+    /// Draws a background stripe under every visual line of the section, cycling
+    /// through `colors`, like zebra striping of tables and logs.
+    ///
+    /// Stripes span the section bounds horizontally (or the line glyphs with unbounded
+    /// width) and the line height vertically. All stripes are drawn under all queued
+    /// text.
+    fn line_backgrounds(self, colors: &'a [Color]) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.line_backgrounds = colors;
+        styled
+    }
+
+    /// Draws a line of `color` under the glyphs of every visual line of the section.
+    ///
+    /// The line position and thickness are derived from the font ascent, as
+    /// **ab_glyph** doesn't expose the font's underline metrics. Like line backgrounds,
+    /// decorations are drawn under all queued text.
+    fn underline(self, color: Color) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.underline = Some(color);
+        styled
+    }
+
+    /// Draws a line of `color` through the middle of the lowercase letters of every
+    /// visual line of the section, see [`Self::underline()`].
+    fn strikethrough(self, color: Color) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.strikethrough = Some(color);
+        styled
+    }
+
+    /// Offsets each glyph by up to `position` pixels and rotates it by up to `rotation`
+    /// radians, for a handwritten look.
+    ///
+    /// Glyph perturbations are derived from the `seed` and the glyph index, so they
+    /// stay the same from frame to frame and the text doesn't shimmer. Measurement still
+    /// uses the unperturbed layout.
+    fn jitter(self, seed: u64, position: f32, rotation: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.jitter = Some(Jitter {
+            seed,
+            position,
+            rotation,
+        });
+        styled
+    }
+
+    /// Places the section into the depth `layer`, higher layers being closer. All
+    /// section text gets the *z* of the layer (see [`TextBrush::layer_z()`]), replacing
+    /// its own *z*.
+    ///
+    /// Needs a brush built with a *depth_stencil*
+    /// ([`BrushBuilder::with_depth_stencil()`]) to have any effect.
+    ///
+    /// [`TextBrush::layer_z()`]: crate::TextBrush::layer_z
+    /// [`BrushBuilder::with_depth_stencil()`]: crate::BrushBuilder::with_depth_stencil
+    fn layer(self, layer: u32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.layer = Some(layer);
+        styled
+    }
+
+    /// Fades glyphs out as they near the `edge`, like the top and bottom of a scrolling
+    /// list. Glyph opacity falls linearly from full at `falloff` pixels inside the edge
+    /// to zero at the edge, measured from the glyph center. Glyphs past the edge are
+    /// hidden.
+    ///
+    /// Each side ([`FadeEdge::Top`], [`FadeEdge::Bottom`], ...) can be set once, call
+    /// this again with another side to fade towards multiple edges.
+    fn edge_fade(self, edge: FadeEdge, falloff: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.edge_fades[edge.index()] = Some((edge, falloff));
+        styled
+    }
+
+    /// Gives all digits (`0` to `9`) the advance of the widest digit, so rapidly
+    /// changing numbers like counters and scoreboards don't jitter in width. Digits
+    /// are centered within the wider advance.
+    ///
+    /// **ab_glyph** can't apply the font's `tnum` feature, so digit advances are
+    /// overridden after layout instead, shifting the following glyphs of the line.
+    /// [`TextBrush::glyph_bounds()`] uses the tabular advances, but wrapping and
+    /// horizontal alignment still use the proportional ones.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    fn tabular_figures(self, tabular: bool) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.tabular_figures = tabular;
+        styled
+    }
+
+    /// Adds `spacing` pixels (negative to tighten) between neighbouring glyphs of each
+    /// line, also known as tracking, e.g. for stylized headings.
+    ///
+    /// Applied after layout, so lines keep their horizontal alignment, but wrapping
+    /// still uses the original advances. [`TextBrush::glyph_bounds()`] includes the
+    /// spacing.
+    ///
+    /// Defaults to `0.0`.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    fn letter_spacing(self, spacing: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.letter_spacing = spacing;
+        styled
+    }
+
+    /// Advances the pen at every `\t` to the next tab stop, placed every `width` pixels
+    /// from the section `screen_position`, so columns of monospaced text line up.
+    /// Consecutive tabs each advance to the following stop, a tab at the start of a
+    /// line to the first one. Tabs themselves aren't drawn.
+    ///
+    /// Applied after layout, like [`Self::letter_spacing()`], so it's meant for left
+    /// aligned text and wrapping still uses the original tab advance. Tabs are kept even
+    /// with [`BrushBuilder::sanitize_control_chars()`]. For columns with their own
+    /// positions and alignments, see [`Columns`](crate::Columns).
+    ///
+    /// Defaults to `0.0` (tabs are laid out as regular glyphs).
+    ///
+    /// [`BrushBuilder::sanitize_control_chars()`]:
+    /// crate::BrushBuilder::sanitize_control_chars
+    fn tab_width(self, width: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.tab_width = width;
+        styled
+    }
+
+    /// Multiplies the vertical advance between lines by `multiplier`, e.g. `1.5` for
+    /// more readable paragraphs, without changing the glyph sizes.
+    ///
+    /// Applied after layout, moving every line away from the first one, so the first
+    /// line keeps its position and bottom or center aligned sections grow downwards.
+    /// [`TextBrush::glyph_bounds()`], [`TextBrush::measure()`], line backgrounds and
+    /// decorations use the adjusted lines.
+    ///
+    /// Defaults to `1.0`.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    /// [`TextBrush::measure()`]: crate::TextBrush::measure
+    fn line_height(self, multiplier: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.line_height = multiplier;
+        styled
+    }
+
+    /// Sets the direction in which the section glyphs advance.
+    ///
+    /// With [`LayoutMode::Vertical`], every line of the text becomes a column of
+    /// upright glyphs, each horizontally centered and advanced by its vertical advance
+    /// (or the font height if the font has none). The section `screen_position` is the
+    /// top right corner of the first column, following columns are placed to the
+    /// left of it, each as wide as the font height.
+    ///
+    /// The text is laid out horizontally first, so bounds, wrapping and alignment
+    /// should be left at their defaults, start new columns with line breaks instead.
+    /// Vertical glyph variants (`vert` feature) aren't applied.
+    ///
+    /// Defaults to [`LayoutMode::Horizontal`].
+    fn layout_mode(self, mode: LayoutMode) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.layout_mode = mode;
+        styled
+    }
+
+    /// Draws a drop shadow under the section glyphs, offset by `offset` pixels, in
+    /// the provided `color` (its alpha multiplied by the glyph alpha).
+    ///
+    /// Shadow glyphs are clipped by the section bounds like the text itself. Blurred
+    /// shadows aren't supported, but [`TextBrush::set_dilation()`] can soften them.
+    ///
+    /// [`TextBrush::set_dilation()`]: crate::TextBrush::set_dilation
+    fn shadow(self, offset: (f32, f32), color: Color) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.shadow = Some((offset, color));
+        styled
+    }
+
+    /// Fills the section with a color gradient, from each text color at the start
+    /// to the `end_color` at the end of the laid out glyphs in the `direction`.
+    ///
+    /// Colors are interpolated across the glyph quads, so a single glyph shows a
+    /// part of the gradient too. Alpha changes like [`Self::reveal()`] and
+    /// [`Self::edge_fade()`] are applied on top of it.
+    fn gradient(
+        self,
+        end_color: Color,
+        direction: GradientDirection,
+    ) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.gradient = Some((end_color, direction));
+        styled
+    }
+
+    /// Transforms the section glyphs by the 2x2 `matrix` (rows, mapping `(x, y)` to
+    /// `(m[0][0] * x + m[0][1] * y, m[1][0] * x + m[1][1] * y)`) and moves them by
+    /// `translation` pixels afterwards, e.g. for rotated or mirrored labels.
+    ///
+    /// The anchor (transform origin) is the section `screen_position`, so the
+    /// [`Layout`](glyph_brush::Layout) alignment decides around which point of the text
+    /// it is rotated or scaled, e.g. centered alignment rotates around the text center.
+    /// Rotating by `angle` radians (clockwise on screen) uses
+    /// `[[cos, -sin], [sin, cos]]`.
+    ///
+    /// Applied on the GPU after layout and clipping by the section bounds, so
+    /// measurement ([`TextBrush::glyph_bounds()`]), hit testing and line backgrounds
+    /// ignore it.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    fn transform(
+        self,
+        matrix: [[f32; 2]; 2],
+        translation: (f32, f32),
+    ) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.transform = Some(Transform2D {
+            matrix,
+            translation,
+        });
+        styled
+    }
+
+    /// Lays the section out at the origin and moves it to its `screen_position` in the
+    /// vertex shader, keeping glyph coordinates small for sections far from the origin,
+    /// like labels in a huge scrolled world, where `f32` glyph positions lose
+    /// precision and glyphs jitter.
+    ///
+    /// Measurement ([`TextBrush::glyph_bounds()`]) and hit testing are unaffected, they
+    /// keep using the `screen_position`.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    fn local_origin(self) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.local_origin = true;
+        styled
+    }
+
+    /// Wraps lines at `width` pixels, replacing the width of the section `bounds` for
+    /// layout, without clipping the glyphs to it. Text flows to any height.
+    ///
+    /// Glyphs are only clipped if a [`Self::clip_rect()`] is set too, the section
+    /// `bounds` are ignored for both wrapping and clipping.
+    ///
+    /// Defaults to the section `bounds` width.
+    fn wrap_width(self, width: f32) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.wrap_width = Some(width);
+        styled
+    }
+
+    /// Clips the section glyphs to `rect`, in the same pixel coordinates as the section
+    /// position, instead of the section `bounds`. Glyphs overlapping it are cropped
+    /// like by the bounds.
+    ///
+    /// Only clipping is affected, wrapping still uses the section `bounds` width, or
+    /// [`Self::wrap_width()`] if set, which takes precedence for layout while the
+    /// `rect` takes precedence for clipping. [`TextBrush::glyph_bounds()`] is clipped
+    /// to the `rect` as well.
+    ///
+    /// Defaults to the section `bounds`.
+    ///
+    /// [`TextBrush::glyph_bounds()`]: crate::TextBrush::glyph_bounds
+    fn clip_rect(self, rect: Rect) -> StyledSection<'a> {
+        let mut styled = self.into();
+        styled.clip_rect = Some(rect);
+        styled
+    }
+}
+
+impl<'a, S: Into<StyledSection<'a>>> SectionExt<'a> for S {}
+
+/// Deterministic per-glyph perturbation, see [`SectionExt::jitter()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Jitter {
+    pub seed: u64,
+    pub position: f32,
+    pub rotation: f32,
+}
+
+impl Jitter {
+    /// Returns the `(x, y)` offset and rotation of the glyph at `index`.
+    pub fn perturbation(&self, index: usize) -> ((f32, f32), f32) {
+        let mut state = self.seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        // SplitMix64, mapped to -1.0..1.0.
+        let mut next = || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            (z >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+        };
+        let offset = (next() * self.position, next() * self.position);
+        (offset, next() * self.rotation)
+    }
+}
+
+/// Section transform, see [`SectionExt::transform()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Transform2D {
+    /// Row-major 2x2 matrix.
+    pub matrix: [[f32; 2]; 2],
+    /// Translation in pixels, applied after the matrix.
+    pub translation: (f32, f32),
+}
+
+impl Transform2D {
+    /// Returns the column-major matrix and the translation of glyph vertices, which
+    /// keep the `anchor` point in place before translating.
+    pub fn vertex_data(self, (ax, ay): (f32, f32)) -> ([f32; 4], [f32; 2]) {
+        let [[a, b], [c, d]] = self.matrix;
+        let (x, y) = self.translation;
+        let translation = [ax + x - (a * ax + b * ay), ay + y - (c * ax + d * ay)];
+        ([a, c, b, d], translation)
+    }
+}
+
+/// Per-glyph data used by the inner glyph brush in place of [`glyph_brush::Extra`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct GlyphExtra {
+    pub color: Color,
+    /// Color at the end of the glyph quad in the `gradient` direction.
+    pub color_end: Color,
+    pub gradient: GradientDirection,
+    pub z: f32,
+    /// Rotation around the glyph center in radians.
+    pub rotation: f32,
+    /// Section transform applied after the rotation, a column-major 2x2 matrix and a
+    /// translation.
+    pub transform: [f32; 4],
+    pub translation: [f32; 2],
+}
+
+impl Hash for GlyphExtra {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.color.map(f32::to_bits).hash(state);
+        self.color_end.map(f32::to_bits).hash(state);
+        self.gradient.hash(state);
+        self.z.to_bits().hash(state);
+        self.rotation.to_bits().hash(state);
+        self.transform.map(f32::to_bits).hash(state);
+        self.translation.map(f32::to_bits).hash(state);
+    }
+}
+
+impl From<Extra> for GlyphExtra {
+    #[inline]
+    fn from(Extra { color, z }: Extra) -> Self {
+        Self {
+            color,
+            color_end: color,
+            gradient: GradientDirection::Horizontal,
+            z,
+            rotation: 0.0,
+            transform: [1.0, 0.0, 0.0, 1.0],
+            translation: [0.0; 2],
+        }
+    }
+}
+
+/// Converts the `section` to a section with [`GlyphExtra`], borrowing its text.
+pub(crate) fn glyph_section<'a>(section: &Section<'a>) -> Section<'a, GlyphExtra> {
+    Section {
+        screen_position: section.screen_position,
+        bounds: section.bounds,
+        layout: section.layout,
+        text: section
+            .text
+            .iter()
+            .map(|t| Text {
+                text: t.text,
+                scale: t.scale,
+                font_id: t.font_id,
+                extra: t.extra.into(),
+            })
+            .collect(),
+    }
+}
@@ -7,55 +7,149 @@ struct VertexInput {
     @location(4) color: vec4<f32>,
 }
 
+// Optional per instance styling, read from a second vertex buffer by `vs_styled`.
+struct StyleInput {
+    // Rotation around the quad center in radians.
+    @location(5) rotation: f32,
+    // Gradient end color, at the right or bottom quad edge.
+    @location(6) color_end: vec4<f32>,
+    // Gradient direction, vertical if non zero.
+    @location(7) vertical: f32,
+    // Section transform, a column-major 2x2 matrix and a translation.
+    @location(8) transform: vec4<f32>,
+    @location(9) translation: vec2<f32>,
+}
+
 struct Matrix {
     v: mat4x4<f32>,
 }
 
-@group(0) @binding(0)
-var<uniform> ortho: Matrix;
+// `ortho: Matrix` is declared by `matrix_uniform.wgsl`, or by
+// `matrix_push_constant.wgsl` when the device supports push constants.
+
+struct Effect {
+    fill_color: vec4<f32>,
+    outline_color: vec4<f32>,
+    outline_width: f32,
+    mode: u32,
+    // Coverage max filter radius in cache texels.
+    dilation: f32,
+    // Glyph x coordinates are supersampled 3 times for subpixel rendering if non zero.
+    subpixel: u32,
+    // Region the mask or fill texture is stretched over (min.xy, max.xy).
+    mask_rect: vec4<f32>,
+    // Shimmer sweep phase and highlight band width in pixels.
+    time: f32,
+    band_width: f32,
+    // Vertex colors are converted from sRGB to linear if non zero.
+    srgb: u32,
+    // Opacity multiplier of everything drawn.
+    alpha: f32,
+    // Emboss strength and light direction (x, y).
+    emboss: vec4<f32>,
+    // Colors are output with premultiplied alpha if non zero.
+    premultiplied: u32,
+    // Glyph coverage exponent, below 1.0 darkens thin glyphs.
+    gamma: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> effect: Effect;
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_pos: vec2<f32>,
     @location(1) color: vec4<f32>,
+    // Glyph region in the cache texture (min.xy, max.xy).
+    @location(2) @interpolate(flat) tex_rect: vec4<f32>,
+    // Cache texture coordinates covered by a single pixel.
+    @location(3) @interpolate(flat) tex_per_px: vec2<f32>,
+    // Position within the mask rect, inside when in 0..1.
+    @location(4) mask_pos: vec2<f32>,
+}
+
+// Converts the color channels of the sRGB encoded `color` to linear ones.
+fn srgb_to_linear(color: vec4<f32>) -> vec4<f32> {
+    let low = color.rgb / 12.92;
+    let high = pow((color.rgb + 0.055) / 1.055, vec3<f32>(2.4));
+    return vec4<f32>(select(high, low, color.rgb <= vec3<f32>(0.04045)), color.a);
 }
 
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
+    let identity = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+    return vertex(in, StyleInput(0.0, in.color, 0.0, identity, vec2<f32>(0.0)));
+}
+
+@vertex
+fn vs_styled(in: VertexInput, style: StyleInput) -> VertexOutput {
+    return vertex(in, style);
+}
+
+fn vertex(in: VertexInput, style: StyleInput) -> VertexOutput {
     var out: VertexOutput;
 
+    // Converts glyph coordinates to pixels.
+    let scale = vec2<f32>(select(1.0, 1.0 / 3.0, effect.subpixel != 0u), 1.0);
+
+    let size = (in.bottom_right - in.top_left.xy) * scale;
+    let tex_size = in.tex_bottom_right - in.tex_top_left;
+    let tex_per_px = select(vec2<f32>(0.0), tex_size / size, size > vec2<f32>(0.0));
+
+    // Outlines need room around the glyph.
+    let expand = effect.outline_width;
+    let tex_top_left = in.tex_top_left - tex_per_px * expand;
+    let tex_bottom_right = in.tex_bottom_right + tex_per_px * expand;
+
     var pos: vec2<f32>;
-    var left: f32 = in.top_left.x;
-    var right: f32 = in.bottom_right.x;
-    var top: f32 = in.top_left.y;
-    var bottom: f32 = in.bottom_right.y;
+    var left: f32 = in.top_left.x * scale.x - expand;
+    var right: f32 = in.bottom_right.x * scale.x + expand;
+    var top: f32 = in.top_left.y - expand;
+    var bottom: f32 = in.bottom_right.y + expand;
 
     switch (in.vertex_index) {
         case 0u: {
             pos = vec2<f32>(left, top);
-            out.tex_pos = in.tex_top_left;
+            out.tex_pos = tex_top_left;
             break;
         }
         case 1u: {
             pos = vec2<f32>(right, top);
-            out.tex_pos = vec2<f32>(in.tex_bottom_right.x, in.tex_top_left.y);
+            out.tex_pos = vec2<f32>(tex_bottom_right.x, tex_top_left.y);
             break;
         }
         case 2u: {
             pos = vec2<f32>(left, bottom);
-            out.tex_pos = vec2<f32>(in.tex_top_left.x, in.tex_bottom_right.y);
+            out.tex_pos = vec2<f32>(tex_top_left.x, tex_bottom_right.y);
             break;
         }
         case 3u: {
             pos = vec2<f32>(right, bottom);
-            out.tex_pos = in.tex_bottom_right;
+            out.tex_pos = tex_bottom_right;
             break;
         }
         default: {}
     }
 
+    let center = (in.top_left.xy + in.bottom_right) * scale * 0.5;
+    let rotation = mat2x2<f32>(
+        cos(style.rotation), sin(style.rotation),
+        -sin(style.rotation), cos(style.rotation),
+    );
+    pos = center + rotation * (pos - center);
+    pos = mat2x2<f32>(style.transform.xy, style.transform.zw) * pos + style.translation;
+
     out.clip_position = ortho.v * vec4<f32>(pos, in.top_left.z, 1.0);
-    out.color = in.color;
+    // Vertices 1 and 3 are on the right edge, 2 and 3 on the bottom one.
+    let end = select(in.vertex_index % 2u == 1u, in.vertex_index >= 2u, style.vertical != 0.0);
+    out.color = select(in.color, style.color_end, end);
+    if (effect.srgb != 0u) {
+        out.color = srgb_to_linear(out.color);
+    }
+    out.tex_rect = vec4<f32>(in.tex_top_left, in.tex_bottom_right);
+    out.tex_per_px = tex_per_px;
+    let mask_size = effect.mask_rect.zw - effect.mask_rect.xy;
+    out.mask_pos = (pos - effect.mask_rect.xy) / max(mask_size, vec2<f32>(0.0001));
     return out;
 }
 
@@ -63,10 +157,215 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 var texture: texture_2d<f32>;
 @group(0) @binding(2)
 var tex_sampler: sampler;
+// Straight alpha RGBA images of color glyphs, like emoji.
+@group(0) @binding(3)
+var color_texture: texture_2d<f32>;
+
+@group(2) @binding(0)
+var mask_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var mask_sampler: sampler;
+
+// Samples glyph coverage, treating everything outside of the glyph region as empty.
+fn coverage(pos: vec2<f32>, rect: vec4<f32>) -> f32 {
+    let inside = all(pos >= rect.xy) && all(pos <= rect.zw);
+    return select(0.0, textureSampleLevel(texture, tex_sampler, pos, 0.0).r, inside);
+}
+
+// Largest coverage found within `radius` (in cache texture coordinates) of `pos`.
+fn dilated_coverage(pos: vec2<f32>, rect: vec4<f32>, radius: vec2<f32>) -> f32 {
+    var value: f32 = coverage(pos, rect);
+    for (var i: i32 = 0; i < 16; i++) {
+        let angle = f32(i) * 0.39269908;
+        let dir = vec2<f32>(cos(angle), sin(angle));
+        value = max(value, coverage(pos + dir * radius, rect));
+        value = max(value, coverage(pos + dir * radius * 0.5, rect));
+    }
+    return value;
+}
+
+// Color glyphs have texture coordinates offset by 2.0 (`COLOR_TEX_OFFSET` in
+// `emoji.rs`), into the color glyph texture.
+fn is_color_glyph(rect: vec4<f32>) -> bool {
+    return rect.x >= 2.0;
+}
+
+// Image color of a color glyph, multiplied by the vertex color.
+fn color_glyph(in: VertexOutput) -> vec4<f32> {
+    let pos = in.tex_pos - vec2<f32>(2.0);
+    let rect = in.tex_rect - vec4<f32>(2.0);
+    let inside = all(pos >= rect.xy) && all(pos <= rect.zw);
+    var color = textureSampleLevel(color_texture, tex_sampler, pos, 0.0);
+    if (effect.srgb != 0u) {
+        color = srgb_to_linear(color);
+    }
+    return select(vec4<f32>(0.0), color * in.color, inside);
+}
+
+// Glyph coverage with the brush-wide dilation applied.
+fn glyph_coverage(pos: vec2<f32>, rect: vec4<f32>) -> f32 {
+    // Solid quads have negative texture coordinates.
+    if (rect.x < 0.0) {
+        return 1.0;
+    }
+    if (effect.dilation <= 0.0) {
+        return gamma_corrected(coverage(pos, rect));
+    }
+    let radius = effect.dilation / vec2<f32>(textureDimensions(texture));
+    return gamma_corrected(dilated_coverage(pos, rect, radius));
+}
+
+// Coverage raised to the brush-wide gamma.
+fn gamma_corrected(value: f32) -> f32 {
+    if (effect.gamma == 1.0 || value <= 0.0) {
+        return value;
+    }
+    return pow(value, effect.gamma);
+}
+
+// Lighting of the glyph surface, with the coverage gradient as its slope. Flat
+// surfaces are lit with 1.0.
+fn emboss_shade(pos: vec2<f32>, rect: vec4<f32>) -> f32 {
+    if (effect.emboss.x == 0.0 || rect.x < 0.0) {
+        return 1.0;
+    }
+    let texel = 1.0 / vec2<f32>(textureDimensions(texture));
+    let dx = coverage(pos + vec2<f32>(texel.x, 0.0), rect)
+        - coverage(pos - vec2<f32>(texel.x, 0.0), rect);
+    let dy = coverage(pos + vec2<f32>(0.0, texel.y), rect)
+        - coverage(pos - vec2<f32>(0.0, texel.y), rect);
+    let normal = normalize(vec3<f32>(vec2<f32>(-dx, -dy) * effect.emboss.x, 1.0));
+    let light = normalize(vec3<f32>(effect.emboss.yz, 1.0));
+    return dot(normal, light) / light.z;
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var alpha: f32 = textureSample(texture, tex_sampler, in.tex_pos).r;
+    let color = effect_color(in);
+    let alpha = color.a * effect.alpha;
+    let rgb = select(color.rgb, color.rgb * alpha, effect.premultiplied != 0u);
+    return vec4<f32>(rgb, alpha);
+}
 
-    return vec4<f32>(in.color.rgb, in.color.a * alpha);
+// Color of the fragment drawn with the current effect mode.
+fn effect_color(in: VertexOutput) -> vec4<f32> {
+    if (is_color_glyph(in.tex_rect)) {
+        return color_glyph(in);
+    }
+    switch (effect.mode) {
+        // Outlined
+        case 1u: {
+            let fill = glyph_coverage(in.tex_pos, in.tex_rect) * effect.fill_color.a;
+            let texel = effect.dilation / vec2<f32>(textureDimensions(texture));
+            let radius = in.tex_per_px * effect.outline_width + texel;
+            let ring = dilated_coverage(in.tex_pos, in.tex_rect, radius)
+                * effect.outline_color.a * (1.0 - fill);
+            let alpha = fill + ring;
+            let rgb = (effect.fill_color.rgb * fill + effect.outline_color.rgb * ring)
+                / max(alpha, 0.0001);
+            return vec4<f32>(rgb, alpha);
+        }
+        // Outlined, keeping the glyph colors.
+        case 6u: {
+            let fill = glyph_coverage(in.tex_pos, in.tex_rect) * in.color.a;
+            let texel = effect.dilation / vec2<f32>(textureDimensions(texture));
+            let radius = in.tex_per_px * effect.outline_width + texel;
+            let ring = dilated_coverage(in.tex_pos, in.tex_rect, radius)
+                * effect.outline_color.a * (1.0 - fill);
+            let alpha = fill + ring;
+            let rgb = (in.color.rgb * fill + effect.outline_color.rgb * ring)
+                / max(alpha, 0.0001);
+            return vec4<f32>(rgb, alpha);
+        }
+        // Hollow
+        case 5u: {
+            let fill = glyph_coverage(in.tex_pos, in.tex_rect);
+            let texel = effect.dilation / vec2<f32>(textureDimensions(texture));
+            let radius = in.tex_per_px * effect.outline_width + texel;
+            let ring = dilated_coverage(in.tex_pos, in.tex_rect, radius) * (1.0 - fill);
+            return vec4<f32>(effect.outline_color.rgb, effect.outline_color.a * ring);
+        }
+        // Masked
+        case 2u: {
+            let inside = all(in.mask_pos >= vec2<f32>(0.0))
+                && all(in.mask_pos <= vec2<f32>(1.0));
+            let mask = textureSampleLevel(mask_texture, mask_sampler, in.mask_pos, 0.0).a;
+            let alpha = glyph_coverage(in.tex_pos, in.tex_rect)
+                * select(0.0, mask, inside);
+            return vec4<f32>(in.color.rgb, in.color.a * alpha);
+        }
+        // Image filled, `fill_color` is the tint.
+        case 3u: {
+            let fill = textureSampleLevel(mask_texture, mask_sampler, in.mask_pos, 0.0)
+                * in.color * effect.fill_color;
+            let alpha = glyph_coverage(in.tex_pos, in.tex_rect);
+            return vec4<f32>(fill.rgb, fill.a * alpha);
+        }
+        // Shimmer, `mask_rect` holds the text bounds.
+        case 4u: {
+            let span = effect.mask_rect.z - effect.mask_rect.x;
+            let x = in.mask_pos.x * span;
+            let center = fract(effect.time) * (span + effect.band_width * 2.0)
+                - effect.band_width;
+            let distance = abs(x - center) / max(effect.band_width, 0.0001);
+            let highlight = smoothstep(0.0, 1.0, 1.0 - clamp(distance, 0.0, 1.0));
+            let color = mix(effect.fill_color, effect.outline_color, highlight);
+            let alpha = glyph_coverage(in.tex_pos, in.tex_rect);
+            return vec4<f32>(color.rgb, color.a * alpha);
+        }
+        default: {
+            let alpha = glyph_coverage(in.tex_pos, in.tex_rect);
+            let shade = emboss_shade(in.tex_pos, in.tex_rect);
+            return vec4<f32>(in.color.rgb * shade, in.color.a * alpha);
+        }
+    }
+}
+
+// Coverage of the red, green and blue subpixels, sampled from the horizontally
+// supersampled glyph with a light filter against color fringes.
+fn subpixel_coverage(pos: vec2<f32>, rect: vec4<f32>) -> vec3<f32> {
+    // Solid quads have negative texture coordinates.
+    if (rect.x < 0.0) {
+        return vec3<f32>(1.0);
+    }
+    let texel = vec2<f32>(1.0 / f32(textureDimensions(texture).x), 0.0);
+    let c0 = coverage(pos - texel * 2.0, rect);
+    let c1 = coverage(pos - texel, rect);
+    let c2 = coverage(pos, rect);
+    let c3 = coverage(pos + texel, rect);
+    let c4 = coverage(pos + texel * 2.0, rect);
+    let filtered = vec3<f32>(
+        c0 + c1 * 2.0 + c2,
+        c1 + c2 * 2.0 + c3,
+        c2 + c3 * 2.0 + c4,
+    ) * 0.25;
+    return vec3<f32>(
+        gamma_corrected(filtered.r),
+        gamma_corrected(filtered.g),
+        gamma_corrected(filtered.b),
+    );
+}
+
+@fragment
+fn fs_subpixel_mask(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (is_color_glyph(in.tex_rect)) {
+        let alpha = color_glyph(in).a * effect.alpha;
+        return vec4<f32>(vec3<f32>(alpha), 0.0);
+    }
+    let coverage = subpixel_coverage(in.tex_pos, in.tex_rect)
+        * in.color.a * effect.alpha;
+    return vec4<f32>(coverage, 0.0);
+}
+
+@fragment
+fn fs_subpixel_color(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (is_color_glyph(in.tex_rect)) {
+        let color = color_glyph(in);
+        let alpha = color.a * effect.alpha;
+        return vec4<f32>(color.rgb * alpha, alpha);
+    }
+    let coverage = subpixel_coverage(in.tex_pos, in.tex_rect)
+        * in.color.a * effect.alpha;
+    let alpha = max(coverage.r, max(coverage.g, coverage.b));
+    return vec4<f32>(in.color.rgb * coverage, alpha);
 }
@@ -0,0 +1,74 @@
+use glyph_brush::SectionGlyph;
+
+/// Run of glyphs shaped by an external text shaper, queued with
+/// [`TextBrush::queue_shaped()`](crate::TextBrush::queue_shaped) instead of being laid
+/// out by **glyph_brush**. Needed for ligatures, contextual forms and complex scripts
+/// like Arabic or Indic ones.
+///
+/// The `byte_index` of every glyph is the byte offset of its cluster in the shaped
+/// text, mapping glyphs back to the text, e.g. for hit testing. Runs of shapers other
+/// than **rustybuzz** can be created with [`ShapedRun::new()`].
+#[derive(Debug, Clone, Default)]
+pub struct ShapedRun {
+    glyphs: Vec<SectionGlyph>,
+}
+
+impl ShapedRun {
+    /// Creates a run of already positioned `glyphs`.
+    #[inline]
+    pub fn new(glyphs: Vec<SectionGlyph>) -> Self {
+        Self { glyphs }
+    }
+
+    /// Converts the output `buffer` of [`rustybuzz::shape()`] into a run, with the
+    /// first glyph origin at `origin` (on the baseline).
+    ///
+    /// The `buffer` must have been shaped with the face of the `font` with `font_id`,
+    /// its font unit positions are scaled to `scale` using the `font` metrics.
+    #[cfg(feature = "shaping")]
+    pub fn from_rustybuzz<F, S>(
+        buffer: &rustybuzz::GlyphBuffer,
+        font: &F,
+        font_id: glyph_brush::FontId,
+        scale: S,
+        origin: (f32, f32),
+    ) -> Self
+    where
+        F: glyph_brush::ab_glyph::Font,
+        S: Into<glyph_brush::ab_glyph::PxScale>,
+    {
+        use glyph_brush::ab_glyph::{point, GlyphId, ScaleFont};
+
+        let scale = scale.into();
+        let scaled = font.as_scaled(scale);
+        let (h_factor, v_factor) = (scaled.h_scale_factor(), scaled.v_scale_factor());
+
+        let mut pen = (0.0, 0.0);
+        let glyphs = buffer
+            .glyph_infos()
+            .iter()
+            .zip(buffer.glyph_positions())
+            .map(|(info, position)| {
+                // Font units point up, screen coordinates down.
+                let x = origin.0 + (pen.0 + position.x_offset as f32) * h_factor;
+                let y = origin.1 - (pen.1 + position.y_offset as f32) * v_factor;
+                pen.0 += position.x_advance as f32;
+                pen.1 += position.y_advance as f32;
+                SectionGlyph {
+                    section_index: 0,
+                    byte_index: info.cluster as usize,
+                    glyph: GlyphId(info.glyph_id as u16)
+                        .with_scale_and_position(scale, point(x, y)),
+                    font_id,
+                }
+            })
+            .collect();
+        Self { glyphs }
+    }
+
+    /// Returns the glyphs of the run.
+    #[inline]
+    pub fn glyphs(&self) -> &[SectionGlyph] {
+        &self.glyphs
+    }
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use wgpu_text::{
+    glyph_brush::{ab_glyph::FontRef, FontId, Section, Text},
+    GrowthPolicy,
+};
+
+fn section(text: &str) -> Section<'_> {
+    Section::default().add_text(Text::new(text).with_scale(32.0))
+}
+
+#[test]
+fn replaced_font_glyphs_are_rasterized_again() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let static_text = brush
+        .create_static(&device, &queue, vec![section("Reload")])
+        .unwrap();
+    assert!(brush.cache_stats().uploaded_glyphs > 0);
+    brush
+        .queue(&device, &queue, vec![section("Reload")])
+        .unwrap();
+    assert_eq!(brush.cache_stats().uploaded_glyphs, 0);
+
+    let font = FontRef::try_from_slice(common::FONT).unwrap();
+    brush.replace_font(FontId(0), font).unwrap();
+    brush
+        .queue(&device, &queue, vec![section("Reload")])
+        .unwrap();
+
+    assert!(brush.cache_stats().uploaded_glyphs > 0);
+    assert!(!brush.is_static_valid(&static_text));
+}
+
+#[test]
+fn quantized_scales_share_cached_glyphs() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::builder()
+        .scale_quantization(4.0)
+        // Reuses glyphs at any subpixel position.
+        .draw_cache_position_tolerance(2.0)
+        .build(&device, common::SIZE, common::SIZE, common::FORMAT);
+    let section =
+        |scale| Section::default().add_text(Text::new("Zoom").with_scale(scale));
+
+    brush.queue(&device, &queue, vec![section(32.5)]).unwrap();
+    assert!(brush.cache_stats().uploaded_glyphs > 0);
+    let small = brush.measure(section(32.5)).unwrap();
+    brush.queue(&device, &queue, vec![section(33.0)]).unwrap();
+    let large = brush.measure(section(33.0)).unwrap();
+
+    assert_eq!(brush.cache_stats().uploaded_glyphs, 0);
+    assert!(large.width() > small.width());
+}
+
+#[test]
+fn replacing_an_unknown_font_keeps_the_cache() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let static_text = brush
+        .create_static(&device, &queue, vec![section("Kept")])
+        .unwrap();
+
+    let font = FontRef::try_from_slice(common::FONT).unwrap();
+    assert!(brush.replace_font(FontId(1), font).is_err());
+    assert!(brush.is_static_valid(&static_text));
+}
+
+#[test]
+fn failed_growth_keeps_the_cache_dimensions() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::builder()
+        .initial_cache_size((64, 64))
+        .with_max_cache_size((256, 256))
+        .build(&device, common::SIZE, common::SIZE, common::FORMAT);
+    let ascii: String = (' '..='~').collect();
+    let huge = Section::default().add_text(Text::new(&ascii).with_scale(120.0));
+
+    assert!(brush.queue(&device, &queue, vec![huge]).is_err());
+    let texture = brush.cache_texture().size();
+    assert_eq!(brush.cache_dimensions(), (64, 64));
+    assert_eq!((texture.width, texture.height), (64, 64));
+}
+
+#[test]
+fn small_text_is_queued_after_exceeding_the_max_cache_size() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::builder()
+        .initial_cache_size((64, 64))
+        .with_max_cache_size((256, 256))
+        .build(&device, common::SIZE, common::SIZE, common::FORMAT);
+    let view =
+        common::target(&device, common::FORMAT, 1).create_view(&Default::default());
+    let ascii: String = (' '..='~').collect();
+    let huge = Section::default().add_text(Text::new(&ascii).with_scale(120.0));
+    let small = Section::default().add_text(Text::new("abc").with_scale(16.0));
+
+    assert!(brush.queue(&device, &queue, vec![huge]).is_err());
+    common::validated(&device, || {
+        brush.queue(&device, &queue, Vec::<Section>::new()).unwrap();
+        brush.queue(&device, &queue, vec![small]).unwrap();
+        common::draw(&device, &queue, &brush, &view);
+    });
+    assert_eq!(brush.vertices().len(), 3);
+}
+
+#[test]
+fn small_growth_steps_grow_by_an_eighth() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let ascii: String = (' '..='~').collect();
+    let grown = |policy| {
+        let mut brush = common::builder()
+            .initial_cache_size((64, 64))
+            .cache_growth(policy)
+            .build(&device, common::SIZE, common::SIZE, common::FORMAT);
+        let text = Section::default().add_text(Text::new(&ascii).with_scale(48.0));
+        brush.queue(&device, &queue, vec![text]).unwrap();
+        brush.cache_dimensions()
+    };
+
+    assert_eq!(grown(GrowthPolicy::Step(1)), grown(GrowthPolicy::Gradual));
+    assert_eq!(grown(GrowthPolicy::Step(0)), grown(GrowthPolicy::Gradual));
+}
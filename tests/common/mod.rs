@@ -0,0 +1,181 @@
+//! Headless device helpers of the integration tests.
+#![allow(dead_code)]
+
+use wgpu_text::{glyph_brush::ab_glyph::FontRef, BrushBuilder, TextBrush};
+
+pub const FONT: &[u8] = include_bytes!("../../examples/fonts/DejaVuSans.ttf");
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+pub const SIZE: u32 = 256;
+
+/// Returns a device of the default adapter, or `None` if there is no adapter, in
+/// which case tests needing a device are skipped.
+pub fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    device_with(wgpu::Features::empty(), wgpu::Limits::default())
+}
+
+/// Returns a device with `features` and `limits`, or `None` if the adapter doesn't
+/// support them.
+pub fn device_with(
+    features: wgpu::Features,
+    limits: wgpu::Limits,
+) -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(
+        instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+    );
+    let Some(adapter) = adapter else {
+        eprintln!("no adapter available, skipping");
+        return None;
+    };
+    let descriptor = wgpu::DeviceDescriptor {
+        label: None,
+        features,
+        limits,
+    };
+    pollster::block_on(adapter.request_device(&descriptor, None)).ok()
+}
+
+pub fn builder() -> BrushBuilder<FontRef<'static>> {
+    BrushBuilder::using_font_bytes(FONT).unwrap()
+}
+
+pub fn brush(device: &wgpu::Device) -> TextBrush<FontRef<'static>> {
+    builder().build(device, SIZE, SIZE, FORMAT)
+}
+
+/// Returns a new `SIZE` x `SIZE` render target of `format` with `samples` samples.
+pub fn target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    samples: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: samples,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Returns a view of a new single sampled `SIZE` x `SIZE` depth texture of `format`.
+pub fn depth(device: &wgpu::Device, format: wgpu::TextureFormat) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&Default::default())
+}
+
+/// Reads the RGBA8 pixel at `(x, y)` of the `texture`.
+pub fn read_pixel(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    (x, y): (u32, u32),
+) -> [u8; 4] {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 4,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            ..texture.as_image_copy()
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout::default(),
+        },
+        wgpu::Extent3d::default(),
+    );
+    submit(device, queue, encoder);
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let pixel = slice.get_mapped_range()[..].try_into().unwrap();
+    pixel
+}
+
+/// Runs `f`, panicking on any validation error it causes.
+pub fn validated<T>(device: &wgpu::Device, f: impl FnOnce() -> T) -> T {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let result = f();
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        panic!("{error:?}");
+    }
+    result
+}
+
+/// Begins a render pass into the `color` view (and the `depth` view if provided, with
+/// its stencil aspect if it has one), clearing them.
+pub fn pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    color: &'a wgpu::TextureView,
+    depth: Option<(&'a wgpu::TextureView, bool)>,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: color,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: depth.map(|(view, stencil)| {
+            wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: stencil.then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: true,
+                }),
+            }
+        }),
+    })
+}
+
+/// Submits the `encoder` and waits for the device to finish it.
+pub fn submit(device: &wgpu::Device, queue: &wgpu::Queue, encoder: wgpu::CommandEncoder) {
+    queue.submit([encoder.finish()]);
+    device.poll(wgpu::Maintain::Wait);
+}
+
+/// Draws the `brush` text into the `view` and submits the pass.
+pub fn draw(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    brush: &TextBrush<FontRef<'static>>,
+    view: &wgpu::TextureView,
+) {
+    let mut encoder = device.create_command_encoder(&Default::default());
+    brush.draw(&mut pass(&mut encoder, view, None));
+    submit(device, queue, encoder);
+}
@@ -0,0 +1,280 @@
+mod common;
+
+use wgpu_text::{
+    glyph_brush::{Section, Text},
+    SectionExt, StyledSection, VertexStyle,
+};
+
+fn section(text: &str) -> Section<'_> {
+    Section::default()
+        .with_screen_position((20.0, 20.0))
+        .add_text(Text::new(text).with_scale(32.0))
+}
+
+#[test]
+fn multisampled_target_is_drawn() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let multisample = wgpu::MultisampleState {
+        count: 4,
+        ..Default::default()
+    };
+    let view =
+        common::target(&device, common::FORMAT, 4).create_view(&Default::default());
+
+    common::validated(&device, || {
+        let mut brush = common::builder().with_multisample(multisample).build(
+            &device,
+            common::SIZE,
+            common::SIZE,
+            common::FORMAT,
+        );
+        brush.queue(&device, &queue, vec![section("MSAA")]).unwrap();
+        common::draw(&device, &queue, &brush, &view);
+    });
+}
+
+/// Full block glyph covering its whole cell, drawn in `color` at depth `z`.
+fn block(color: [f32; 4], z: f32) -> Section<'static> {
+    Section::default()
+        .with_screen_position((20.0, 20.0))
+        .add_text(
+            Text::new("\u{2588}")
+                .with_scale(128.0)
+                .with_color(color)
+                .with_z(z),
+        )
+}
+
+#[test]
+fn closer_sections_are_drawn_over_further_ones() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let format = wgpu::TextureFormat::Depth32Float;
+    let mut brush = common::builder()
+        .with_depth_stencil(Some(wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: Default::default(),
+            bias: Default::default(),
+        }))
+        .build(&device, common::SIZE, common::SIZE, common::FORMAT);
+    let target = common::target(&device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+    let depth = common::depth(&device, format);
+    let (red, green) = ([1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]);
+
+    for (sections, expected) in [
+        (vec![block(red, 0.2), block(green, 0.6)], [255, 0, 0, 255]),
+        (vec![block(red, 0.6), block(green, 0.2)], [0, 255, 0, 255]),
+    ] {
+        brush.queue(&device, &queue, sections).unwrap();
+        let depths: Vec<f32> = brush.vertices().iter().map(|v| v.top_left[2]).collect();
+        assert_eq!(depths.len(), 2);
+        assert_ne!(depths[0], depths[1]);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        brush.draw(&mut common::pass(
+            &mut encoder,
+            &view,
+            Some((&depth, false)),
+        ));
+        common::submit(&device, &queue, encoder);
+
+        let pixel = common::read_pixel(&device, &queue, &target, (50, 80));
+        assert_eq!(pixel, expected);
+    }
+}
+
+#[test]
+fn push_constant_matrix_is_drawn() {
+    let limits = wgpu::Limits {
+        max_push_constant_size: 64,
+        ..Default::default()
+    };
+    let Some((device, queue)) =
+        common::device_with(wgpu::Features::PUSH_CONSTANTS, limits)
+    else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let target = common::target(&device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+
+    common::validated(&device, || {
+        let green = block([0.0, 1.0, 0.0, 1.0], 0.0);
+        brush.queue(&device, &queue, vec![green]).unwrap();
+        common::draw(&device, &queue, &brush, &view);
+    });
+    let pixel = common::read_pixel(&device, &queue, &target, (50, 80));
+    assert_eq!(pixel, [0, 255, 0, 255]);
+}
+
+fn stencil_state(compare: wgpu::CompareFunction) -> wgpu::DepthStencilState {
+    let face = wgpu::StencilFaceState {
+        compare,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Replace,
+    };
+    wgpu::DepthStencilState {
+        format: wgpu::TextureFormat::Depth24PlusStencil8,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0xff,
+        },
+        bias: Default::default(),
+    }
+}
+
+#[test]
+fn stencil_masks_text() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let build = |compare| {
+        common::builder()
+            .with_depth_stencil(Some(stencil_state(compare)))
+            .build(&device, common::SIZE, common::SIZE, common::FORMAT)
+    };
+    let mut mask = build(wgpu::CompareFunction::Always);
+    let mut text = build(wgpu::CompareFunction::Equal);
+    let target = common::target(&device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+    let depth = common::depth(&device, wgpu::TextureFormat::Depth24PlusStencil8);
+
+    let small = Section::default()
+        .with_screen_position((20.0, 20.0))
+        .add_text(Text::new("\u{2588}").with_scale(64.0));
+    mask.queue(&device, &queue, vec![small]).unwrap();
+    let large = block([0.0, 1.0, 0.0, 1.0], 0.0);
+    text.queue(&device, &queue, vec![large]).unwrap();
+    common::validated(&device, || {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut rpass = common::pass(&mut encoder, &view, Some((&depth, true)));
+            mask.draw_with_stencil(&mut rpass, 1);
+            text.draw_with_stencil(&mut rpass, 1);
+        }
+        common::submit(&device, &queue, encoder);
+    });
+
+    let inside = common::read_pixel(&device, &queue, &target, (40, 50));
+    let outside = common::read_pixel(&device, &queue, &target, (100, 140));
+    assert_eq!(inside, [0, 255, 0, 255]);
+    assert_eq!(outside, [0, 0, 0, 255]);
+}
+
+#[test]
+fn changed_vertices_are_uploaded() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let target = common::target(&device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+    let (red, green) = ([1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]);
+    let sections = |color| vec![section("Text"), block(color, 0.0)];
+
+    for staged in [false, true] {
+        common::validated(&device, || {
+            brush.queue(&device, &queue, sections(red)).unwrap();
+            let mut encoder = device.create_command_encoder(&Default::default());
+            match staged {
+                true => {
+                    brush
+                        .queue_staged(&device, &queue, &mut encoder, sections(green))
+                        .unwrap();
+                    brush.finish_staging();
+                }
+                false => brush.queue(&device, &queue, sections(green)).unwrap(),
+            }
+            // Only the block changed color.
+            assert_eq!(brush.last_frame_stats().vertices_uploaded, 1);
+            brush.draw(&mut common::pass(&mut encoder, &view, None));
+            common::submit(&device, &queue, encoder);
+            brush.recall_staging();
+        });
+        let pixel = common::read_pixel(&device, &queue, &target, (50, 80));
+        assert_eq!(pixel, [0, 255, 0, 255]);
+    }
+}
+
+/// Draws the styled block `section` and returns the center of its unrotated quad and
+/// its style along with the target texture.
+fn draw_block(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    section: StyledSection,
+) -> ((f32, f32), VertexStyle, wgpu::Texture) {
+    let mut brush = common::brush(device);
+    let target = common::target(device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+
+    common::validated(device, || {
+        brush.queue(device, queue, vec![section]).unwrap();
+        common::draw(device, queue, &brush, &view);
+    });
+    let [vertex] = brush.vertices() else {
+        panic!("{} vertices", brush.vertices().len());
+    };
+    let center = (
+        (vertex.top_left[0] + vertex.bottom_right[0]) * 0.5,
+        (vertex.top_left[1] + vertex.bottom_right[1]) * 0.5,
+    );
+    (center, brush.vertex_styles()[0], target)
+}
+
+#[test]
+fn section_transform_rotates_around_the_anchor() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let anchor = (150.0, 40.0);
+    // A quarter turn clockwise on screen.
+    let section = block([0.0, 1.0, 0.0, 1.0], 0.0)
+        .with_screen_position(anchor)
+        .transform([[0.0, -1.0], [1.0, 0.0]], (0.0, 0.0));
+
+    let ((x, y), _, target) = draw_block(&device, &queue, section);
+    let rotated = (anchor.0 - (y - anchor.1), anchor.1 + (x - anchor.0));
+
+    let pixel = |(x, y): (f32, f32)| {
+        common::read_pixel(&device, &queue, &target, (x as u32, y as u32))
+    };
+    assert_eq!(pixel(rotated), [0, 255, 0, 255]);
+    assert_eq!(pixel((x, y)), [0, 0, 0, 255]);
+}
+
+#[test]
+fn glyph_rotates_around_its_center() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let section = block([0.0, 1.0, 0.0, 1.0], 0.0)
+        .with_screen_position((60.0, 40.0))
+        .jitter(5, 0.0, std::f32::consts::FRAC_PI_2);
+
+    let ((x, y), style, target) = draw_block(&device, &queue, section);
+    // About 48 degrees counterclockwise for this seed, turning one way or the other
+    // moves different corners out of the unrotated glyph.
+    assert!((-0.9..-0.8).contains(&style.rotation), "{}", style.rotation);
+    let (sin, cos) = style.rotation.sin_cos();
+    // Point below the center once rotated.
+    let below = 48.0;
+    let rotated = (x - sin * below, y + cos * below);
+
+    let pixel = |(x, y): (f32, f32)| {
+        common::read_pixel(&device, &queue, &target, (x as u32, y as u32))
+    };
+    assert_eq!(pixel(rotated), [0, 255, 0, 255]);
+    // Bottom right corner of the unrotated glyph.
+    assert_eq!(pixel((x + 38.0, y + 50.0)), [0, 0, 0, 255]);
+}
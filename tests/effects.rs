@@ -0,0 +1,68 @@
+mod common;
+
+use wgpu::util::DeviceExt;
+use wgpu_text::glyph_brush::{
+    ab_glyph::{point, Rect},
+    Section, Text,
+};
+
+/// Returns a view of a 1x1 texture of the RGBA8 `color`.
+fn texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    color: [u8; 4],
+) -> wgpu::TextureView {
+    let descriptor = wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d::default(),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: common::FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    };
+    device
+        .create_texture_with_data(queue, &descriptor, &color)
+        .create_view(&Default::default())
+}
+
+#[test]
+fn masked_and_image_filled_text_use_their_own_textures() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let target = common::target(&device, common::FORMAT, 1);
+    let view = target.create_view(&Default::default());
+    let whole = Rect {
+        min: point(0.0, 0.0),
+        max: point(common::SIZE as f32, common::SIZE as f32),
+    };
+    let block = Section::default()
+        .with_screen_position((20.0, 20.0))
+        .add_text(
+            Text::new("\u{2588}")
+                .with_scale(128.0)
+                .with_color([1.0, 1.0, 1.0, 1.0]),
+        );
+
+    common::validated(&device, || {
+        brush.queue(&device, &queue, vec![block]).unwrap();
+        let red = texture(&device, &queue, [255, 0, 0, 255]);
+        let white = [1.0, 1.0, 1.0, 1.0];
+        brush.set_image_filled(&device, &queue, &red, whole, white);
+        let transparent = texture(&device, &queue, [0, 0, 0, 0]);
+        brush.set_masked_by(&device, &queue, &transparent, whole);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut rpass = common::pass(&mut encoder, &view, None);
+            brush.draw_image_filled(&mut rpass);
+            brush.draw_masked_by(&mut rpass);
+        }
+        common::submit(&device, &queue, encoder);
+    });
+    let pixel = common::read_pixel(&device, &queue, &target, (50, 80));
+    assert_eq!(pixel, [255, 0, 0, 255]);
+}
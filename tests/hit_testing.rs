@@ -0,0 +1,29 @@
+mod common;
+
+use wgpu_text::glyph_brush::{ab_glyph::point, Section, Text};
+
+#[test]
+fn caret_rect_and_index_at_round_trip() {
+    let Some((device, _)) = common::device() else {
+        return;
+    };
+    let brush = common::brush(&device);
+    let texts = ["Hello ", "big", " text\nsecond line\n\nü last"];
+    let section = Section::default()
+        .with_screen_position((10.0, 20.0))
+        .add_text(Text::new(texts[0]).with_scale(24.0))
+        .add_text(Text::new(texts[1]).with_scale(40.0))
+        .add_text(Text::new(texts[2]).with_scale(24.0));
+    let text = texts.concat();
+
+    for (index, _) in text.char_indices().chain([(text.len(), ' ')]) {
+        // Empty lines have no glyphs and can't be hit.
+        if text[..index].ends_with('\n') && text[index..].starts_with('\n') {
+            continue;
+        }
+        let caret = brush.caret_rect(&section, index).unwrap();
+        let center = point(caret.min.x, (caret.min.y + caret.max.y) / 2.0);
+
+        assert_eq!(brush.index_at(&section, center), index, "{caret:?}");
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use wgpu_text::{
+    glyph_brush::{
+        ab_glyph::{point, GlyphId, Rect},
+        FontId, Layout, Section, Text,
+    },
+    SectionExt,
+};
+
+fn section(text: &str) -> Section<'_> {
+    Section::default()
+        .with_screen_position((20.0, 20.0))
+        .add_text(Text::new(text).with_scale(32.0))
+}
+
+#[test]
+fn plain_text_has_no_vertex_styles() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+
+    brush
+        .queue(&device, &queue, vec![section("Plain")])
+        .unwrap();
+    assert!(!brush.vertices().is_empty());
+    assert!(brush.vertex_styles().is_empty());
+}
+
+#[test]
+fn styled_and_plain_text_is_drawn() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let view =
+        common::target(&device, common::FORMAT, 1).create_view(&Default::default());
+    let (sin, cos) = 0.5f32.sin_cos();
+
+    common::validated(&device, || {
+        let rotated = section("Rotated").transform([[cos, -sin], [sin, cos]], (0.0, 0.0));
+        brush.queue(&device, &queue, vec![rotated]).unwrap();
+        assert_eq!(brush.vertex_styles().len(), brush.vertices().len());
+        common::draw(&device, &queue, &brush, &view);
+
+        brush
+            .queue(&device, &queue, vec![section("Plain")])
+            .unwrap();
+        assert!(brush.vertex_styles().is_empty());
+        common::draw(&device, &queue, &brush, &view);
+    });
+}
+
+#[test]
+fn glyphs_outside_bounds_are_dropped() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let text = "A line of text wider than its bounds";
+
+    brush.queue(&device, &queue, vec![section(text)]).unwrap();
+    let unbounded = brush.vertices().len();
+    // Single line, so the bounds cut the line instead of wrapping it.
+    let bounded = section(text)
+        .with_layout(Layout::default_single_line())
+        .with_bounds((100.0, f32::INFINITY));
+    brush.queue(&device, &queue, vec![bounded]).unwrap();
+    let bounded = brush.vertices().len();
+
+    assert!(bounded > 0);
+    assert!(bounded < unbounded, "{bounded} >= {unbounded}");
+}
+
+#[test]
+fn glyphs_of_unknown_fonts_are_not_queued() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let color = [0.0, 0.0, 0.0, 1.0];
+
+    let unknown = brush.queue_glyph(GlyphId(36), FontId(1), 32.0, (20.0, 40.0), color);
+    assert!(unknown.is_err());
+    brush.queue(&device, &queue, Vec::<Section>::new()).unwrap();
+    assert!(brush.vertices().is_empty());
+
+    brush
+        .queue_glyph(GlyphId(36), FontId(0), 32.0, (20.0, 40.0), color)
+        .unwrap();
+    brush.queue(&device, &queue, Vec::<Section>::new()).unwrap();
+    assert_eq!(brush.vertices().len(), 1);
+}
+
+#[test]
+fn highlights_stay_queued_when_queueing_fails() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let highlight = Rect {
+        min: point(10.0, 10.0),
+        max: point(60.0, 30.0),
+    };
+
+    brush.queue_highlight(highlight, [1.0, 1.0, 0.0, 1.0]);
+    let unknown =
+        section("Text").with_text(vec![Text::new("Text").with_font_id(FontId(1))]);
+    assert!(brush.queue(&device, &queue, vec![unknown]).is_err());
+
+    brush.queue(&device, &queue, vec![section("Text")]).unwrap();
+    let highlighted = brush.vertices().len();
+    brush.queue(&device, &queue, vec![section("Text")]).unwrap();
+    assert_eq!(highlighted, brush.vertices().len() + 1);
+}
+
+#[test]
+fn glyphs_without_bitmap_strikes_are_rasterized() {
+    let Some((device, queue)) = common::device() else {
+        return;
+    };
+    let mut brush = common::brush(&device);
+    let mut strikes = common::builder().prefer_bitmap_strikes(true).build(
+        &device,
+        common::SIZE,
+        common::SIZE,
+        common::FORMAT,
+    );
+
+    brush.queue(&device, &queue, vec![section("Text")]).unwrap();
+    strikes
+        .queue(&device, &queue, vec![section("Text")])
+        .unwrap();
+    assert_eq!(strikes.vertices().len(), 4);
+    assert_eq!(strikes.vertices(), brush.vertices());
+}